@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use chrono::NaiveDateTime;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    day::DayRepository,
+    task::{Task, TaskRepository},
+    Error, Result,
+};
+
+/// A problem with the stored tasks, which the commands do not create, but which
+/// may come from older versions, imports or edits of the database.
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    kind: IssueKind,
+    tasks: Vec<Task<u64>>,
+    message: String,
+    fixed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The day of the task does not exist.
+    OrphanedDay,
+    /// The task ends at or before its start.
+    InvalidDuration,
+    /// More than one task is running.
+    MultipleActive,
+    /// Two tasks overlap.
+    Overlap,
+}
+
+/// Looks for problems and repairs them with `fix`, later checks see the
+/// repaired tasks. Tasks are repaired as follows:
+///
+/// - tasks of missing days are moved to the day of their start
+/// - tasks without a duration are deleted, they can be restored
+/// - running tasks, except the latest, end at the start of the next task
+/// - overlapping tasks end at the start of the following task
+pub fn check(
+    day_repository: &DayRepository,
+    task_repository: &TaskRepository,
+    fix: bool,
+) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+
+    for task in task_repository.all_tasks()? {
+        match day_repository.day(task.day()) {
+            Err(Error::NotFound(_)) => {}
+            result => {
+                result?;
+                continue;
+            }
+        }
+        let message = format!("the day with the id {} does not exist", task.day());
+        if fix {
+            let day = day_repository.from_date(task.start().date())?;
+            task_repository.assign_day(task.clone(), day)?;
+        }
+        issues.push(Issue::new(IssueKind::OrphanedDay, vec![task], message, fix));
+    }
+
+    for task in task_repository.all_tasks()? {
+        let Some(end) = task.end().filter(|end| *end <= task.start()) else {
+            continue;
+        };
+        let message = match end == task.start() {
+            true => "the task has no duration".to_owned(),
+            false => "the task ends before it starts".to_owned(),
+        };
+        let fixed = fix && has_day(day_repository, &task);
+        if fixed {
+            let task = task_repository.resolve(task.clone())?;
+            task_repository.delete(task)?;
+        }
+        issues.push(Issue::new(
+            IssueKind::InvalidDuration,
+            vec![task],
+            message,
+            fixed,
+        ));
+    }
+
+    let tasks = task_repository.all_tasks()?;
+    let active: Vec<_> = tasks.iter().filter(|task| task.is_active()).collect();
+    // The running tasks before the latest one are reported here, not as overlaps.
+    let mut stale_ids = Vec::new();
+    if active.len() > 1 {
+        let mut fixed = fix;
+        for stale in &active[..active.len() - 1] {
+            stale_ids.push(stale.id());
+            let next = tasks
+                .iter()
+                .find(|task| task.start() > stale.start() && task.id() != stale.id());
+            match next {
+                Some(next) if fix && has_day(day_repository, stale) => {
+                    let stale = task_repository.resolve((*stale).clone())?;
+                    task_repository.update_end(stale, next.start())?;
+                }
+                _ => fixed = false,
+            }
+        }
+        let message = format!("{} tasks are running at the same time", active.len());
+        let tasks = active.into_iter().cloned().collect();
+        issues.push(Issue::new(IssueKind::MultipleActive, tasks, message, fixed));
+    }
+
+    // After repairs the tasks are read again, the stale tasks have ended then.
+    // Otherwise they never end and would overlap every later task.
+    let tasks = match fix {
+        true => {
+            stale_ids.clear();
+            task_repository.all_tasks()?
+        }
+        false => tasks,
+    };
+    let mut latest: Option<&Task<u64>> = None;
+    for task in tasks
+        .iter()
+        .filter(|task| task.end().is_none_or(|end| end > task.start()))
+    {
+        let is_stale = stale_ids.contains(&task.id());
+        let Some(previous) = latest.filter(|previous| end_of(previous) > task.start()) else {
+            if !is_stale {
+                latest = Some(task);
+            }
+            continue;
+        };
+        let message = format!("the task {} overlaps the task {}", previous.id(), task.id());
+        let fixed = fix && previous.start() < task.start() && has_day(day_repository, previous);
+        if fixed {
+            let trimmed = task_repository.resolve(previous.clone())?;
+            task_repository.update_end(trimmed, task.start())?;
+        }
+        issues.push(Issue::new(
+            IssueKind::Overlap,
+            vec![previous.clone(), task.clone()],
+            message,
+            fixed,
+        ));
+        if !is_stale && (fixed || end_of(task) > end_of(previous)) {
+            latest = Some(task);
+        }
+    }
+
+    Ok(issues)
+}
+
+impl Issue {
+    fn new(kind: IssueKind, tasks: Vec<Task<u64>>, message: String, fixed: bool) -> Self {
+        Self {
+            kind,
+            tasks,
+            message,
+            fixed,
+        }
+    }
+
+    pub fn kind(&self) -> IssueKind {
+        self.kind
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &Task<u64>> {
+        self.tasks.iter()
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.fixed
+    }
+}
+
+/// The end of a task, running tasks never end.
+fn end_of(task: &Task<u64>) -> NaiveDateTime {
+    task.end().unwrap_or(NaiveDateTime::MAX)
+}
+
+/// Whether the day of the task exists, tasks of missing days are only repaired
+/// once their day is.
+fn has_day(day_repository: &DayRepository, task: &Task<u64>) -> bool {
+    day_repository.day(task.day()).is_ok()
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "issue kind={} tasks={} fixed={} message=\"{}\"",
+            self.kind,
+            self.tasks.iter().map(|task| task.id()).join(","),
+            self.fixed,
+            self.message
+        )
+    }
+}
+
+impl Display for IssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::OrphanedDay => "orphaned_day",
+            Self::InvalidDuration => "invalid_duration",
+            Self::MultipleActive => "multiple_active",
+            Self::Overlap => "overlap",
+        };
+        write!(f, "{}", value)
+    }
+}
@@ -0,0 +1,60 @@
+use std::env;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+
+use crate::{Error, Result};
+
+/// The environment variable, which fixes the current time, e.g. for scripts.
+pub const NOW_VARIABLE: &str = "TTRACE_NOW";
+
+const NOW_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// The source of the current time for the repositories and the running task.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Clock {
+    /// The local time of the system.
+    #[default]
+    System,
+    /// A fixed time, which never changes.
+    Fixed(NaiveDateTime),
+}
+
+impl Clock {
+    /// The system clock, unless `TTRACE_NOW` is set to a local date and time
+    /// like `2024-05-01 17:30`.
+    pub fn from_env() -> Result<Self> {
+        match env::var(NOW_VARIABLE) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(value.trim()),
+            _ => Ok(Self::System),
+        }
+    }
+
+    pub fn now(&self) -> NaiveDateTime {
+        match self {
+            Self::System => Local::now().naive_local(),
+            Self::Fixed(now) => *now,
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date()
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        NOW_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .map(Self::Fixed)
+            .ok_or_else(|| {
+                Error::InvalidTime(format!(
+                    "could not read {}='{}', expected e.g. 2024-05-01 17:30",
+                    NOW_VARIABLE, value
+                ))
+            })
+    }
+}
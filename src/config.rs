@@ -1,36 +1,237 @@
-use std::{fs, path::PathBuf};
+use std::{
+    env,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
 
+use chrono::{DateTime, Weekday};
 use expanduser::expanduser;
-use eyre::Context;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{rounding::Rounding, Error, Result};
+
+/// The environment variable, which points to the config file to use.
+pub const CONFIG_VARIABLE: &str = "TTRACE_CONFIG";
+
+/// The config file of older versions, it is read if no other file exists.
+const LEGACY_CONFIG: &str = "~/.ttrack.json";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// The directory of the database.
     path: PathBuf,
+    week_start: Weekday,
+    time_format: String,
+    rounding: Rounding,
+    default_tags: Vec<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            path: expanduser("~/.local/state/ttrack").unwrap(),
+            path: expand("~/.local/state/ttrack"),
+            week_start: Weekday::Mon,
+            time_format: "%H:%M".to_owned(),
+            rounding: Rounding::default(),
+            default_tags: Vec::new(),
         }
     }
 }
 
 impl Config {
-    pub fn load() -> eyre::Result<Self> {
-        let config = fs::read_to_string("~/.ttrack.json")
-            .map(|content| {
-                serde_json::from_str(content.as_str())
-                    .wrap_err("ERROR \"could not parse the '~/.ttrack.json' config!\"")
-                    .with_context(|| content)
-            })
-            .unwrap_or(Ok(Self::default()))?;
+    /// Reads the config file at `file_path`, the defaults are used if there is
+    /// no config file.
+    pub fn load() -> Result<Self> {
+        let path = Self::file_path();
+        if path.exists() || env::var_os(CONFIG_VARIABLE).is_some() {
+            return Self::load_from(&path);
+        }
+        let config = Self::default();
         fs::create_dir_all(&config.path)?;
         Ok(config)
     }
 
+    /// Reads the config file, which has to exist, and creates the directory
+    /// of the database.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(Error::Config(format!(
+                "the config file {} does not exist",
+                path.display()
+            )));
+        }
+        let config = Self::read(path)?;
+        fs::create_dir_all(&config.path)?;
+        Ok(config)
+    }
+
+    /// The path of `TTRACE_CONFIG` or the first existing file of
+    /// `$XDG_CONFIG_HOME/ttrace/config.toml`, `$XDG_CONFIG_HOME/ttrace/config.json`
+    /// and `~/.ttrack.json`. Without any file, the toml file is used.
+    pub fn file_path() -> PathBuf {
+        if let Some(path) = env::var_os(CONFIG_VARIABLE).filter(|path| !path.is_empty()) {
+            return expand(&path.to_string_lossy());
+        }
+        let directory = config_directory().join("ttrace");
+        let toml = directory.join("config.toml");
+        let candidates = [
+            toml.clone(),
+            directory.join("config.json"),
+            expand(LEGACY_CONFIG),
+        ];
+        candidates
+            .into_iter()
+            .find(|path| path.exists())
+            .unwrap_or(toml)
+    }
+
+    /// The directory of the config file, profiles are stored next to it.
+    pub fn directory() -> PathBuf {
+        match env::var_os(CONFIG_VARIABLE).filter(|path| !path.is_empty()) {
+            Some(path) => expand(&path.to_string_lossy())
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            None => config_directory().join("ttrace"),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let value = match is_toml(path) {
+            true => toml::from_str(&content).map_err(|error| Error::Config(error.to_string())),
+            false => serde_json::from_str(&content).map_err(Error::from),
+        }
+        .and_then(Self::from_value)
+        .map_err(|error| with_context(format!("could not read {}", path.display()), error))?;
+        Ok(value)
+    }
+
+    /// Writes all settings to the file, the format depends on the extension.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = match is_toml(path) {
+            true => self.to_toml()?,
+            false => serde_json::to_string_pretty(self)?,
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Changes a setting, tables are separated by dots, e.g. `rounding.mode`.
+    /// Lists are separated by commas.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut settings = serde_json::to_value(&*self)?;
+        let setting = key
+            .split('.')
+            .try_fold(&mut settings, |value, name| value.get_mut(name))
+            .filter(|setting| !setting.is_object())
+            .ok_or_else(|| Error::Config(format!("unknown setting: {}", key)))?;
+        *setting = match setting {
+            Value::Array(_) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_owned()))
+                .collect(),
+            Value::Number(_) => value
+                .parse::<u64>()
+                .map(Value::from)
+                .map_err(|_| Error::Config(format!("{} must be a number: {}", key, value)))?,
+            _ => Value::String(value.to_owned()),
+        };
+        *self = Self::from_value(settings)
+            .map_err(|error| with_context(format!("invalid value for {}", key), error))?;
+        Ok(())
+    }
+
+    /// The settings in the format of the toml config file.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).map_err(|error| Error::Config(error.to_string()))
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn week_start(&self) -> Weekday {
+        self.week_start
+    }
+
+    /// The format of times in the interactive output, see `chrono::format::strftime`.
+    pub fn time_format(&self) -> &str {
+        self.time_format.as_str()
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    /// The tags of new tasks, for which no tags are given.
+    pub fn default_tags(&self) -> &[String] {
+        self.default_tags.as_slice()
+    }
+
+    pub fn with_path(self, path: PathBuf) -> Self {
+        Self { path, ..self }
+    }
+
     pub fn database_path(&self) -> PathBuf {
         self.path.join("database.db")
     }
+
+    pub fn backup_path(&self, version: u32) -> PathBuf {
+        self.path.join(format!("database.v{}.backup.db", version))
+    }
+
+    fn from_value(value: Value) -> Result<Self> {
+        let mut config: Self = serde_json::from_value(value)?;
+        config.path = expand(&config.path.to_string_lossy());
+        // Invalid items and items like `%z`, which need a timezone, fail only
+        // when a time is formatted.
+        let mut formatted = String::new();
+        if write!(
+            formatted,
+            "{}",
+            DateTime::UNIX_EPOCH.naive_utc().format(&config.time_format)
+        )
+        .is_err()
+        {
+            return Err(Error::Config(format!(
+                "invalid time format: {}",
+                config.time_format
+            )));
+        }
+        Ok(config)
+    }
+}
+
+/// `$XDG_CONFIG_HOME` or `~/.config`, if it is not set.
+fn config_directory() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| expand("~/.config"))
+}
+
+fn with_context(context: String, error: Error) -> Error {
+    match error {
+        Error::Config(message) => Error::Config(format!("{}: {}", context, message)),
+        Error::Json(error) => Error::Config(format!("{}: {}", context, error)),
+        error => Error::Config(format!("{}: {}", context, error)),
+    }
+}
+
+fn expand(path: &str) -> PathBuf {
+    expanduser(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "toml")
 }
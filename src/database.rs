@@ -2,11 +2,43 @@ use std::rc::Rc;
 
 use rusqlite::{Connection, OpenFlags};
 
-use crate::config::Config;
+use crate::{clock::Clock, config::Config, Result};
 
-pub fn open_database_connection(config: &Config) -> eyre::Result<Rc<Connection>> {
+pub use self::migration::latest_version;
+
+use self::migration::{migrate, version};
+
+mod migration;
+
+/// Opens the database and migrates it to the latest version, the clock
+/// decides which days have passed.
+pub fn open_database_connection(config: &Config, clock: Clock) -> Result<Rc<Connection>> {
     let path = config.database_path();
     let flags = OpenFlags::default();
-    let connection = Connection::open_with_flags(path, flags)?;
+    let mut connection = Connection::open_with_flags(path, flags)?;
+    migrate(&mut connection, config, clock.today())?;
+    Ok(connection.into())
+}
+
+/// Opens the database only for reading, e.g. for frequent calls of status
+/// bars. Nothing is created, if there is no database yet. An outdated database
+/// is migrated like by `open_database_connection`.
+pub fn open_database_read_only(config: &Config, clock: Clock) -> Result<Option<Rc<Connection>>> {
+    let path = config.database_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let connection = Connection::open_with_flags(&path, flags)?;
+    if version(&connection)? != latest_version() {
+        return open_database_connection(config, clock).map(Some);
+    }
+    Ok(Some(connection.into()))
+}
+
+/// Opens a new database in memory, which is dropped with the connection.
+pub fn open_in_memory_database() -> Result<Rc<Connection>> {
+    let mut connection = Connection::open_in_memory()?;
+    migrate(&mut connection, &Config::default(), Clock::System.today())?;
     Ok(connection.into())
 }
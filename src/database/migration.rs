@@ -0,0 +1,162 @@
+use chrono::NaiveDate;
+use rusqlite::{Batch, Connection};
+
+use crate::{config::Config, Error, Result};
+
+struct Migration {
+    version: u32,
+    description: &'static str,
+    statements: &'static str,
+}
+
+/// All migrations in the order they have to be applied. The first migrations
+/// are written to also work on databases, which were created before the
+/// database was versioned. Statements may use `:today` for the current date
+/// of the clock.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create days and tasks",
+        statements: "
+            CREATE TABLE IF NOT EXISTS days (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date DATE NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                day_id INTEGER NOT NULL,
+                start DATE NOT NULL,
+                end DATE,
+                description TEXT NOT NULL
+            );",
+    },
+    Migration {
+        version: 2,
+        description: "create task tags",
+        statements: "
+            CREATE TABLE IF NOT EXISTS task_tags (
+                task_id INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (task_id, tag)
+            );",
+    },
+    Migration {
+        version: 3,
+        description: "store the start and end of tasks as timestamps",
+        // Unfinished tasks of past days are ended at the end of their day,
+        // as they were displayed before.
+        statements: "
+            UPDATE tasks
+            SET end = (SELECT date FROM days WHERE days.id = tasks.day_id) || ' 23:59:59'
+            WHERE end IS null
+                AND start NOT LIKE '____-__-__%'
+                AND (SELECT date FROM days WHERE days.id = tasks.day_id) < :today;
+            UPDATE tasks
+            SET end = (SELECT date FROM days WHERE days.id = tasks.day_id) || ' ' || end
+            WHERE end IS NOT null AND end NOT LIKE '____-__-__%';
+            UPDATE tasks
+            SET start = (SELECT date FROM days WHERE days.id = tasks.day_id) || ' ' || start
+            WHERE start NOT LIKE '____-__-__%';",
+    },
+    Migration {
+        version: 4,
+        description: "soft delete tasks",
+        statements: "ALTER TABLE tasks ADD COLUMN deleted_at DATE;",
+    },
+    Migration {
+        version: 5,
+        description: "journal of operations",
+        statements: "
+            CREATE TABLE operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL,
+                created_at DATE NOT NULL,
+                undone INTEGER NOT NULL
+            );
+            CREATE TABLE operation_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                operation_id INTEGER NOT NULL,
+                task_id INTEGER NOT NULL,
+                before TEXT,
+                after TEXT
+            );",
+    },
+    Migration {
+        version: 6,
+        description: "notes of tasks",
+        statements: "ALTER TABLE tasks ADD COLUMN notes TEXT;",
+    },
+    Migration {
+        version: 7,
+        description: "paused tasks",
+        statements: "ALTER TABLE tasks ADD COLUMN paused INTEGER NOT NULL DEFAULT 0;",
+    },
+];
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+pub fn version(connection: &Connection) -> Result<u32> {
+    let version = connection.query_row("PRAGMA user_version", (), |row| row.get(0))?;
+    Ok(version)
+}
+
+/// Upgrades the database step by step to the latest version. Before anything
+/// is changed, a copy of an existing database is written to the backup path.
+/// Days before `today` count as past days.
+pub fn migrate(connection: &mut Connection, config: &Config, today: NaiveDate) -> Result<()> {
+    let version = version(connection)?;
+    let latest_version = latest_version();
+    if version > latest_version {
+        return Err(Error::UnsupportedDatabaseVersion {
+            version,
+            supported: latest_version,
+        });
+    }
+    if version == latest_version {
+        return Ok(());
+    }
+    let backup_path = config.backup_path(version);
+    if has_tables(connection)? && !backup_path.exists() {
+        connection.execute("VACUUM INTO ?1", (backup_path.to_string_lossy(),))?;
+    }
+    let transaction = connection.transaction()?;
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > version)
+    {
+        execute_migration(&transaction, migration, today).map_err(|source| Error::Migration {
+            version: migration.version,
+            description: migration.description,
+            source,
+        })?;
+    }
+    transaction.pragma_update(None, "user_version", latest_version)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn execute_migration(
+    connection: &Connection,
+    migration: &Migration,
+    today: NaiveDate,
+) -> rusqlite::Result<()> {
+    let mut batch = Batch::new(connection, migration.statements);
+    while let Some(mut statement) = batch.next()? {
+        if let Some(index) = statement.parameter_index(":today")? {
+            statement.raw_bind_parameter(index, today)?;
+        }
+        statement.raw_execute()?;
+    }
+    Ok(())
+}
+
+fn has_tables(connection: &Connection) -> Result<bool> {
+    let count: u32 = connection.query_row(
+        "SELECT count(*) FROM sqlite_master WHERE type='table'",
+        (),
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
@@ -1,96 +1,129 @@
 use std::rc::Rc;
 
-use chrono::{Datelike, Days, Local, NaiveDate, Weekday};
-use eyre::{Context, ContextCompat};
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
 use rusqlite::{Connection, Params, Row};
+use someutil::NaiveWeekExt;
 
 pub use dto::{Day, DayRef, DayReference};
-use someutil::NaiveWeekExt;
+
+use crate::{clock::Clock, Error, Result};
 
 mod dto;
 
 pub struct DayRepository {
     connection: Rc<Connection>,
+    clock: Clock,
 }
 
 impl DayRepository {
-    pub fn new(connection: Rc<Connection>) -> eyre::Result<Self> {
-        let _ = connection.execute(
-            "CREATE TABLE IF NOT EXISTS days (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                date DATE NOT NULL
-            )",
-            (),
-        )?;
-        Ok(Self { connection })
-    }
-
-    pub fn today(&self) -> eyre::Result<Day> {
-        let date = Local::now().date_naive();
+    pub fn new(connection: Rc<Connection>, clock: Clock) -> Self {
+        Self { connection, clock }
+    }
+
+    pub fn today(&self) -> Result<Day> {
+        let date = self.clock.today();
         self.from_date(date)
     }
 
-    pub fn yesterday(&self) -> eyre::Result<Day> {
-        let date = Local::now()
-            .date_naive()
+    pub fn yesterday(&self) -> Result<Day> {
+        let date = self
+            .clock
+            .today()
             .checked_sub_days(Days::new(1))
-            .wrap_err("could not get yesterdays date!")?;
+            .ok_or_else(|| Error::InvalidInput("could not get yesterdays date".to_owned()))?;
         self.from_date(date)
     }
 
-    pub fn complete_week(&self, mut date: NaiveDate) -> eyre::Result<Vec<Day>> {
-        const ONE_DAY: Days = Days::new(1);
-        date.week(Weekday::Mon)
+    pub fn complete_week(&self, date: NaiveDate, week_start: Weekday) -> Result<Vec<Day>> {
+        date.week(week_start)
             .iter_days()
             .map(|date| self.from_date(date))
             .collect()
     }
 
-    pub fn week_till_today(&self) -> eyre::Result<Vec<Day>> {
-        let mut date = Local::now().date_naive();
-        self.week_till_date(date)
+    pub fn week_till_today(&self, week_start: Weekday) -> Result<Vec<Day>> {
+        let date = self.clock.today();
+        self.week_till_date(date, week_start)
     }
 
-    pub fn week_till_date(&self, mut date: NaiveDate) -> eyre::Result<Vec<Day>> {
-        const ONE_DAY: Days = Days::new(1);
-        date.week(Weekday::Mon)
+    pub fn week_till_date(&self, date: NaiveDate, week_start: Weekday) -> Result<Vec<Day>> {
+        date.week(week_start)
             .iter_days()
             .filter(|day| *day <= date)
             .map(|date| self.from_date(date))
             .collect()
     }
 
-    pub fn list_passed_days(&self, count: usize) -> eyre::Result<Vec<Day>> {
+    /// The existing days from the first to the last date, missing days are
+    /// not created.
+    pub fn range(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Day>> {
+        self.query(
+            "SELECT id, date FROM days WHERE date BETWEEN ?1 AND ?2 ORDER BY date",
+            (from, to),
+        )
+    }
+
+    pub fn month(&self, date: NaiveDate) -> Result<Vec<Day>> {
+        let first = date
+            .with_day(1)
+            .ok_or_else(|| invalid_date("the first day of the month"))?;
+        let last = first
+            .checked_add_months(Months::new(1))
+            .and_then(|date| date.pred_opt())
+            .ok_or_else(|| invalid_date("the last day of the month"))?;
+        self.range(first, last.min(self.clock.today()))
+    }
+
+    pub fn year(&self, date: NaiveDate) -> Result<Vec<Day>> {
+        let first = NaiveDate::from_ymd_opt(date.year(), 1, 1)
+            .ok_or_else(|| invalid_date("the first day of the year"))?;
+        let last = NaiveDate::from_ymd_opt(date.year(), 12, 31)
+            .ok_or_else(|| invalid_date("the last day of the year"))?;
+        self.range(first, last.min(self.clock.today()))
+    }
+
+    pub fn list_passed_days(&self, count: usize) -> Result<Vec<Day>> {
         self.query(
             "SELECT id, date FROM days ORDER BY date DESC LIMIT ?1",
             (count,),
         )
     }
 
-    pub fn from_date(&self, date: NaiveDate) -> eyre::Result<Day> {
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_date(&self, date: NaiveDate) -> Result<Day> {
         if let Ok(day) = self.from_date_or_none(&date) {
             return Ok(day);
         }
-        self.insert_from_date(&date);
+        self.insert_from_date(&date)?;
         self.from_date_or_none(&date)
     }
 
-    fn from_date_or_none(&self, date: &NaiveDate) -> eyre::Result<Day> {
+    /// The stored day of the date, unlike `from_date` it is never created.
+    pub fn find(&self, date: NaiveDate) -> Result<Option<Day>> {
+        match self.from_date_or_none(&date) {
+            Ok(day) => Ok(Some(day)),
+            Err(Error::NotFound(_)) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn from_date_or_none(&self, date: &NaiveDate) -> Result<Day> {
         self.get("SELECT id, date FROM days WHERE date = ?1", (date,))
     }
 
-    pub fn resolve(&self, reference: DayReference) -> eyre::Result<Day> {
+    pub fn resolve(&self, reference: DayReference) -> Result<Day> {
         match reference {
             DayReference::Id(id) => self.day(id),
             DayReference::Value(day) => Ok(day),
         }
     }
 
-    pub fn day(&self, id: u64) -> eyre::Result<Day> {
+    pub fn day(&self, id: u64) -> Result<Day> {
         self.get("SELECT id, date FROM days WHERE id = ?1", (id,))
     }
 
-    fn insert_from_date(&self, date: &NaiveDate) -> eyre::Result<()> {
+    fn insert_from_date(&self, date: &NaiveDate) -> Result<()> {
         let _ = self
             .connection
             .execute("INSERT INTO days (date) VALUES (?1)", (date,))?;
@@ -99,28 +132,31 @@ impl DayRepository {
 }
 
 impl DayRepository {
-    fn get(&self, statement: &str, parameters: impl Params) -> eyre::Result<Day> {
+    fn get(&self, statement: &str, parameters: impl Params) -> Result<Day> {
         self.connection
-            .query_row(statement, parameters, day_from_row)
-            .wrap_err("could not query day")
-            .with_context(|| statement.to_owned())
+            .query_row(statement, parameters, |row| day_from_row(row, self.clock))
+            .map_err(|err| match err {
+                rusqlite::Error::QueryReturnedNoRows => Error::NotFound("day".to_owned()),
+                err => Error::Database(err),
+            })
     }
 
-    fn query(&self, query: &str, parameters: impl Params) -> eyre::Result<Vec<Day>> {
-        self.connection
+    fn query(&self, query: &str, parameters: impl Params) -> Result<Vec<Day>> {
+        let days = self
+            .connection
             .prepare(query)?
-            .query_map(parameters, day_from_row)
-            .wrap_err("could not execute sql statement")
-            .with_context(|| query.to_owned())?
-            .into_iter()
-            .collect::<Result<_, _>>()
-            .wrap_err("cannot convert tasks from sql statement")
-            .with_context(|| query.to_owned())
+            .query_map(parameters, |row| day_from_row(row, self.clock))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(days)
     }
 }
 
-pub fn day_from_row(row: &Row) -> rusqlite::Result<Day> {
+fn invalid_date(what: &str) -> Error {
+    Error::InvalidInput(format!("could not get {}", what))
+}
+
+pub fn day_from_row(row: &Row, clock: Clock) -> rusqlite::Result<Day> {
     let id = row.get("id")?;
     let date = row.get("date")?;
-    Ok(Day::new(id, date))
+    Ok(Day::new(id, date, clock))
 }
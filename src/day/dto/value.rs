@@ -1,13 +1,16 @@
 use std::fmt::Display;
 
-use chrono::{Local, NaiveDate, NaiveTime};
-use rusqlite::Row;
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime};
 use serde::Serialize;
 
+use crate::clock::Clock;
+
 #[derive(Debug, Copy, Clone, Serialize)]
 pub struct Day {
     id: u64,
     date: NaiveDate,
+    #[serde(skip)]
+    clock: Clock,
 }
 
 pub trait DayRef {
@@ -16,8 +19,8 @@ pub trait DayRef {
 }
 
 impl Day {
-    pub fn new(id: u64, date: NaiveDate) -> Self {
-        Self { id, date }
+    pub fn new(id: u64, date: NaiveDate, clock: Clock) -> Self {
+        Self { id, date, clock }
     }
 
     pub fn id(&self) -> u64 {
@@ -28,15 +31,21 @@ impl Day {
         self.date
     }
 
+    /// The clock the day was read with, it is used for the running task.
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
     pub fn is_today(&self) -> bool {
-        self.date == Local::now().date_naive()
+        self.date == self.clock.today()
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.date.and_time(NaiveTime::MIN)
     }
 
-    pub fn time(&self) -> NaiveTime {
-        if !self.is_today() {
-            return NaiveTime::from_hms_opt(23, 59, 59).unwrap();
-        }
-        Local::now().time()
+    pub fn end(&self) -> NaiveDateTime {
+        self.start() + Days::new(1)
     }
 }
 
@@ -0,0 +1,80 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("no task is currently active")]
+    NoActiveTask,
+    #[error("could not find the {0}")]
+    NotFound(String),
+    #[error("invalid time: {0}")]
+    InvalidTime(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Overlap(String),
+    #[error("{0}")]
+    Import(String),
+    #[error("config error: {0}")]
+    Config(String),
+    #[error("{0}")]
+    Inconsistent(String),
+    #[error(
+        "the database has version {version} but this version of ttrace only supports up to version {supported}"
+    )]
+    UnsupportedDatabaseVersion { version: u32, supported: u32 },
+    #[error("could not migrate the database to version {version} ({description}): {source}")]
+    Migration {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("csv error: {0}")]
+    Csv(#[from] csv::Error),
+}
+
+impl Error {
+    /// The exit code of the cli for the error, see the README for the list.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::InvalidInput(_) => 2,
+            Self::NoActiveTask => 3,
+            Self::NotFound(_) => 4,
+            Self::InvalidTime(_) => 5,
+            Self::Overlap(_) => 6,
+            Self::Config(_) => 7,
+            Self::Inconsistent(_) => 9,
+            Self::UnsupportedDatabaseVersion { .. }
+            | Self::Migration { .. }
+            | Self::Database(_) => 8,
+            Self::Import(_) | Self::Io(_) | Self::Json(_) | Self::Csv(_) => 1,
+        }
+    }
+
+    /// A stable name of the error, which can be matched by scripts.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NoActiveTask => "no_active_task",
+            Self::NotFound(_) => "not_found",
+            Self::InvalidTime(_) => "invalid_time",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::Overlap(_) => "overlap",
+            Self::Import(_) => "import",
+            Self::Config(_) => "config",
+            Self::Inconsistent(_) => "inconsistent",
+            Self::UnsupportedDatabaseVersion { .. }
+            | Self::Migration { .. }
+            | Self::Database(_) => "database",
+            Self::Io(_) => "io",
+            Self::Json(_) => "json",
+            Self::Csv(_) => "csv",
+        }
+    }
+}
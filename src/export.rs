@@ -0,0 +1,49 @@
+use std::io::Write;
+
+use crate::{task::DayWithTasks, Result};
+
+const CSV_HEADER: [&str; 9] = [
+    "id",
+    "date",
+    "start",
+    "end",
+    "minutes",
+    "hours",
+    "description",
+    "tags",
+    "running",
+];
+
+/// Writes one row per task and day, tasks spanning midnight are split at the
+/// end of the day, so the durations add up to the totals of the days.
+pub fn write_csv(writer: impl Write, days: &[DayWithTasks]) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(writer);
+    writer.write_record(CSV_HEADER)?;
+    for day_with_tasks in days {
+        let day = day_with_tasks.day();
+        for task in day_with_tasks.tasks() {
+            let start = task.start().max(day.start());
+            // A task which ends at or after midnight ends at 24:00 on this day.
+            let end = task.end().map(|end| match end < day.end() {
+                true => end.format("%H:%M").to_string(),
+                false => "24:00".to_owned(),
+            });
+            let delta = task.delta_on(day);
+            writer.write_record([
+                task.id().to_string(),
+                day.date().format("%Y-%m-%d").to_string(),
+                start.format("%H:%M").to_string(),
+                end.unwrap_or_default(),
+                delta.num_minutes().to_string(),
+                format!("{:.2}", delta.num_minutes() as f64 / 60.0),
+                task.description().to_owned(),
+                task.tags().join(","),
+                task.is_active().to_string(),
+            ])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use std::{fmt::Display, io::Read};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    day::{Day, DayRepository},
+    task::{Task, TaskRepository},
+    Error, Result,
+};
+
+/// A finished task read from the export of another application.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntry {
+    start: NaiveDateTime,
+    end: Option<NaiveDateTime>,
+    description: String,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedEntry {
+    entry: ImportEntry,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    dry_run: bool,
+    imported: Vec<Task<Day>>,
+    duplicates: Vec<ImportEntry>,
+    skipped: Vec<SkippedEntry>,
+}
+
+#[derive(Deserialize)]
+struct TimewarriorInterval {
+    start: String,
+    end: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    annotation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TogglRow {
+    #[serde(rename = "Project", default)]
+    project: String,
+    #[serde(rename = "Description", default)]
+    description: String,
+    #[serde(rename = "Start date")]
+    start_date: NaiveDate,
+    #[serde(rename = "Start time")]
+    start_time: NaiveTime,
+    #[serde(rename = "End date")]
+    end_date: NaiveDate,
+    #[serde(rename = "End time")]
+    end_time: NaiveTime,
+    #[serde(rename = "Tags", default)]
+    tags: String,
+}
+
+/// Reads the output of `timew export`. Intervals without an annotation use
+/// their tags as description.
+pub fn parse_timewarrior(reader: impl Read) -> Result<Vec<ImportEntry>> {
+    let intervals: Vec<TimewarriorInterval> = serde_json::from_reader(reader).map_err(|error| {
+        Error::Import(format!("could not parse the timewarrior export: {}", error))
+    })?;
+    intervals
+        .into_iter()
+        .map(|interval| {
+            let start = parse_timewarrior_time(&interval.start)?;
+            let end = interval
+                .end
+                .as_deref()
+                .map(parse_timewarrior_time)
+                .transpose()?;
+            let description = match interval.annotation {
+                Some(annotation) if !annotation.trim().is_empty() => annotation,
+                _ => interval.tags.join(" "),
+            };
+            Ok(ImportEntry {
+                start,
+                end,
+                description,
+                tags: interval.tags,
+            })
+        })
+        .collect()
+}
+
+/// Reads the detailed csv report of Toggl. The project is added to the tags.
+pub fn parse_toggl(reader: impl Read) -> Result<Vec<ImportEntry>> {
+    csv::Reader::from_reader(reader)
+        .deserialize::<TogglRow>()
+        .map(|row| {
+            let row = row.map_err(|error| {
+                Error::Import(format!("could not parse the toggl export: {}", error))
+            })?;
+            let mut tags: Vec<String> = row
+                .tags
+                .split(',')
+                .map(|tag| tag.trim().to_owned())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            if !row.project.trim().is_empty() {
+                tags.push(row.project.trim().to_owned());
+            }
+            Ok(ImportEntry {
+                start: row.start_date.and_time(row.start_time),
+                end: Some(row.end_date.and_time(row.end_time)),
+                description: row.description,
+                tags,
+            })
+        })
+        .collect()
+}
+
+/// Creates the tasks for the entries. Entries matching an existing task are
+/// reported as duplicates, entries overlapping other tasks are skipped.
+pub fn import(
+    entries: Vec<ImportEntry>,
+    dry_run: bool,
+    day_repository: &DayRepository,
+    task_repository: &TaskRepository,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary {
+        dry_run,
+        ..Default::default()
+    };
+    for entry in entries {
+        let Some(end) = entry.end else {
+            summary.skip(entry, "the task is not finished");
+            continue;
+        };
+        if end <= entry.start {
+            summary.skip(entry, "the task ends before it starts");
+            continue;
+        }
+        let overlapping = task_repository.overlapping(entry.start, end)?;
+        if overlapping.iter().any(|task| entry.matches(task)) {
+            summary.duplicates.push(entry);
+            continue;
+        }
+        if let Some(task) = overlapping.first() {
+            let reason = format!("overlaps with the {}", task);
+            summary.skip(entry, reason);
+            continue;
+        }
+        let day = day_repository.from_date(entry.start.date())?;
+        let task = task_repository.add(day, entry.start, end, &entry.description, &entry.tags)?;
+        summary.imported.push(task);
+    }
+    Ok(summary)
+}
+
+impl ImportEntry {
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        self.end
+    }
+
+    pub fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_slice()
+    }
+
+    fn matches(&self, task: &Task<Day>) -> bool {
+        task.start() == self.start
+            && task.end() == self.end
+            && task
+                .description()
+                .eq_ignore_ascii_case(self.description.trim())
+    }
+}
+
+impl ImportSummary {
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn imported(&self) -> impl Iterator<Item = &Task<Day>> {
+        self.imported.iter()
+    }
+
+    pub fn duplicates(&self) -> impl Iterator<Item = &ImportEntry> {
+        self.duplicates.iter()
+    }
+
+    pub fn skipped(&self) -> impl Iterator<Item = &SkippedEntry> {
+        self.skipped.iter()
+    }
+
+    fn skip(&mut self, entry: ImportEntry, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.skipped.push(SkippedEntry { entry, reason });
+    }
+}
+
+impl SkippedEntry {
+    pub fn entry(&self) -> &ImportEntry {
+        &self.entry
+    }
+
+    pub fn reason(&self) -> &str {
+        self.reason.as_str()
+    }
+}
+
+impl Display for ImportEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "entry \"{}\" start={} end=",
+            self.description,
+            self.start.format("%Y-%m-%d %H:%M")
+        )?;
+        match self.end {
+            Some(end) => write!(f, "{}", end.format("%Y-%m-%d %H:%M")),
+            None => write!(f, "..."),
+        }
+    }
+}
+
+impl Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "import imported={} duplicates={} skipped={}",
+            self.imported.len(),
+            self.duplicates.len(),
+            self.skipped.len()
+        )?;
+        if self.dry_run {
+            write!(f, " dry-run")?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_timewarrior_time(value: &str) -> Result<NaiveDateTime> {
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ").map_err(|error| {
+        Error::Import(format!("invalid timewarrior time '{}': {}", value, error))
+    })?;
+    Ok(Utc
+        .from_utc_datetime(&time)
+        .with_timezone(&Local)
+        .naive_local())
+}
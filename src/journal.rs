@@ -0,0 +1,289 @@
+use std::{cell::Cell, rc::Rc};
+
+use rusqlite::{Connection, OptionalExtension, Row};
+
+pub use dto::{Operation, TaskRecord};
+
+use crate::{clock::Clock, Error, Result};
+
+mod dto;
+
+/// Records the changes to tasks made by a command, so that the command can be
+/// undone and redone later.
+pub struct JournalRepository {
+    connection: Rc<Connection>,
+    operation: Cell<Option<u64>>,
+    /// Whether the current operation recorded a change yet.
+    changed: Cell<bool>,
+    clock: Clock,
+}
+
+impl JournalRepository {
+    pub fn new(connection: Rc<Connection>, clock: Clock) -> Self {
+        Self {
+            connection,
+            operation: Cell::new(None),
+            changed: Cell::new(false),
+            clock,
+        }
+    }
+
+    /// Starts a new operation, all changes until `commit` are recorded in it.
+    /// Once it changes a task, undone operations can no longer be redone.
+    pub fn begin(&self, command: &str) -> Result<()> {
+        if self.operation.get().is_some() {
+            return Err(Error::InvalidInput(
+                "an operation was already started".to_owned(),
+            ));
+        }
+        self.connection.execute_batch("BEGIN")?;
+        let now = self.clock.now();
+        self.connection.execute(
+            "INSERT INTO operations (command, created_at, undone) VALUES (?1, ?2, 0)",
+            (command, now),
+        )?;
+        let id = self.connection.last_insert_rowid() as u64;
+        self.operation.set(Some(id));
+        self.changed.set(false);
+        Ok(())
+    }
+
+    /// Finishes the current operation. Operations without changes are dropped.
+    pub fn commit(&self) -> Result<()> {
+        let Some(id) = self.operation.take() else {
+            return Ok(());
+        };
+        self.connection.execute(
+            "DELETE FROM operations
+             WHERE id = ?1 AND NOT EXISTS (
+                SELECT 1 FROM operation_changes WHERE operation_id = ?1
+             )",
+            (id,),
+        )?;
+        self.connection.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    /// Discards all changes of the current operation.
+    pub fn rollback(&self) -> Result<()> {
+        if self.operation.take().is_none() {
+            return Ok(());
+        }
+        self.connection.execute_batch("ROLLBACK")?;
+        Ok(())
+    }
+
+    pub fn snapshot(&self, task_id: u64) -> Result<Option<TaskRecord>> {
+        let Some(mut record) = self
+            .connection
+            .query_row(
+                "SELECT id, day_id, start, end, description, notes, deleted_at, paused
+                 FROM tasks
+                 WHERE id = ?1",
+                (task_id,),
+                record_from_row,
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        record.tags = self
+            .connection
+            .prepare("SELECT tag FROM task_tags WHERE task_id = ?1 ORDER BY tag")?
+            .query_map((task_id,), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(Some(record))
+    }
+
+    /// Records the change of a task in the current operation, the state after
+    /// the change is read from the database.
+    pub fn record(&self, task_id: u64, before: Option<TaskRecord>) -> Result<()> {
+        let Some(operation_id) = self.operation.get() else {
+            return Ok(());
+        };
+        if !self.changed.replace(true) {
+            self.connection.execute_batch(
+                "DELETE FROM operation_changes
+                 WHERE operation_id IN (SELECT id FROM operations WHERE undone = 1);
+                 DELETE FROM operations WHERE undone = 1;",
+            )?;
+        }
+        let after = self.snapshot(task_id)?;
+        let before = before
+            .map(|record| serde_json::to_string(&record))
+            .transpose()?;
+        let after = after
+            .map(|record| serde_json::to_string(&record))
+            .transpose()?;
+        self.connection.execute(
+            "INSERT INTO operation_changes (operation_id, task_id, before, after)
+             VALUES (?1, ?2, ?3, ?4)",
+            (operation_id, task_id, before, after),
+        )?;
+        Ok(())
+    }
+
+    /// Reverts the latest operation, which was not undone yet.
+    pub fn undo(&self) -> Result<Option<Operation>> {
+        let Some(operation) = self.operation_opt(
+            "SELECT id, command, created_at, undone FROM operations
+             WHERE undone = 0
+             ORDER BY id DESC
+             LIMIT 1",
+        )?
+        else {
+            return Ok(None);
+        };
+        let transaction = self.connection.unchecked_transaction()?;
+        let changes = self.changes(operation.id(), "before")?;
+        for (task_id, record) in changes.into_iter().rev() {
+            self.apply(task_id, record)?;
+        }
+        self.connection.execute(
+            "UPDATE operations SET undone = 1 WHERE id = ?1",
+            (operation.id(),),
+        )?;
+        transaction.commit()?;
+        self.operation(operation.id()).map(Some)
+    }
+
+    /// Applies the earliest undone operation again.
+    pub fn redo(&self) -> Result<Option<Operation>> {
+        let Some(operation) = self.operation_opt(
+            "SELECT id, command, created_at, undone FROM operations
+             WHERE undone = 1
+             ORDER BY id ASC
+             LIMIT 1",
+        )?
+        else {
+            return Ok(None);
+        };
+        let transaction = self.connection.unchecked_transaction()?;
+        let changes = self.changes(operation.id(), "after")?;
+        for (task_id, record) in changes {
+            self.apply(task_id, record)?;
+        }
+        self.connection.execute(
+            "UPDATE operations SET undone = 0 WHERE id = ?1",
+            (operation.id(),),
+        )?;
+        transaction.commit()?;
+        self.operation(operation.id()).map(Some)
+    }
+
+    pub fn history(&self, count: usize) -> Result<Vec<Operation>> {
+        let operations = self
+            .connection
+            .prepare(
+                "SELECT id, command, created_at, undone,
+                    (SELECT count(*) FROM operation_changes WHERE operation_id = operations.id)
+                        AS changes
+                 FROM operations
+                 ORDER BY id DESC
+                 LIMIT ?1",
+            )?
+            .query_map((count,), operation_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(operations)
+    }
+}
+
+impl JournalRepository {
+    fn operation(&self, id: u64) -> Result<Operation> {
+        let operation = self.connection.query_row(
+            "SELECT id, command, created_at, undone,
+                    (SELECT count(*) FROM operation_changes WHERE operation_id = operations.id)
+                        AS changes
+                 FROM operations
+                 WHERE id = ?1",
+            (id,),
+            operation_from_row,
+        )?;
+        Ok(operation)
+    }
+
+    fn operation_opt(&self, query: &str) -> Result<Option<Operation>> {
+        let id = self
+            .connection
+            .query_row(query, (), |row| row.get("id"))
+            .optional()?;
+        id.map(|id| self.operation(id)).transpose()
+    }
+
+    fn changes(&self, operation_id: u64, column: &str) -> Result<Vec<(u64, Option<TaskRecord>)>> {
+        let query = format!(
+            "SELECT task_id, {} AS record FROM operation_changes WHERE operation_id = ?1 ORDER BY id",
+            column
+        );
+        self.connection
+            .prepare(&query)?
+            .query_map((operation_id,), |row| {
+                Ok((row.get("task_id")?, row.get::<_, Option<String>>("record")?))
+            })?
+            .map(|change| {
+                let (task_id, record) = change?;
+                let record = record
+                    .map(|record| serde_json::from_str(&record))
+                    .transpose()?;
+                Ok((task_id, record))
+            })
+            .collect()
+    }
+
+    /// Writes the state of a task back, `None` removes the task.
+    fn apply(&self, task_id: u64, record: Option<TaskRecord>) -> Result<()> {
+        self.connection
+            .execute("DELETE FROM task_tags WHERE task_id = ?1", (task_id,))?;
+        let Some(record) = record else {
+            self.connection
+                .execute("DELETE FROM tasks WHERE id = ?1", (task_id,))?;
+            return Ok(());
+        };
+        self.connection.execute(
+            "INSERT OR REPLACE INTO tasks
+                (id, day_id, start, end, description, notes, deleted_at, paused)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            (
+                record.id,
+                record.day_id,
+                record.start,
+                record.end,
+                record.description.as_str(),
+                record.notes.as_deref(),
+                record.deleted_at,
+                record.paused,
+            ),
+        )?;
+        for tag in record.tags.iter() {
+            self.connection.execute(
+                "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                (task_id, tag),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn record_from_row(row: &Row) -> rusqlite::Result<TaskRecord> {
+    Ok(TaskRecord {
+        id: row.get("id")?,
+        day_id: row.get("day_id")?,
+        start: row.get("start")?,
+        end: row.get("end")?,
+        description: row.get("description")?,
+        notes: row.get("notes")?,
+        deleted_at: row.get("deleted_at")?,
+        paused: row.get("paused")?,
+        tags: Vec::new(),
+    })
+}
+
+fn operation_from_row(row: &Row) -> rusqlite::Result<Operation> {
+    Ok(Operation::new(
+        row.get("id")?,
+        row.get("command")?,
+        row.get("created_at")?,
+        row.get("undone")?,
+        row.get("changes")?,
+    ))
+}
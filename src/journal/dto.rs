@@ -0,0 +1,4 @@
+pub use {operation::Operation, task_record::TaskRecord};
+
+mod operation;
+mod task_record;
@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// A command, which changed tasks and can be undone as a whole.
+#[derive(Debug, Clone, Serialize)]
+pub struct Operation {
+    id: u64,
+    command: String,
+    created_at: NaiveDateTime,
+    undone: bool,
+    changes: u64,
+}
+
+impl Operation {
+    pub fn new(
+        id: u64,
+        command: String,
+        created_at: NaiveDateTime,
+        undone: bool,
+        changes: u64,
+    ) -> Self {
+        Self {
+            id,
+            command,
+            created_at,
+            undone,
+            changes,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn command(&self) -> &str {
+        self.command.as_str()
+    }
+
+    pub fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    pub fn is_undone(&self) -> bool {
+        self.undone
+    }
+
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "operation \"{}\" id={} at={} changes={}",
+            self.command,
+            self.id,
+            self.created_at.format("%Y-%m-%d %H:%M"),
+            self.changes
+        )?;
+        if self.undone {
+            write!(f, " undone")?;
+        }
+        Ok(())
+    }
+}
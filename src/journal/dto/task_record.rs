@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of a task row with its tags, as it is stored in the journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub id: u64,
+    pub day_id: u64,
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+    pub description: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub paused: bool,
+    pub tags: Vec<String>,
+}
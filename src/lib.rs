@@ -0,0 +1,34 @@
+//! Track the time spent on tasks.
+//!
+//! Tasks are stored in a SQLite database and belong to the day they were
+//! started on. [`DayRepository`] and [`TaskRepository`] read and change them,
+//! [`DayWithTasks`] bundles the tasks of a day.
+
+pub use {
+    clock::Clock,
+    config::Config,
+    database::open_database_connection,
+    day::{Day, DayRepository},
+    error::{Error, Result},
+    journal::JournalRepository,
+    profile::Profile,
+    task::{DayWithTasks, Task, TaskGroup, TaskRepository},
+    time::TimeOrDelta,
+};
+
+pub mod check;
+pub mod clock;
+pub mod config;
+pub mod database;
+pub mod day;
+pub mod error;
+pub mod export;
+pub mod import;
+pub mod journal;
+pub mod profile;
+pub mod report;
+pub mod rounding;
+pub mod status;
+pub mod summary;
+pub mod task;
+pub mod time;
@@ -1,29 +1,39 @@
-#![allow(unused)]
-
+use std::env;
+use std::fs::File;
+use std::io::stdout;
 use std::process::exit;
+use std::rc::Rc;
 use std::str::FromStr;
 
-use chrono::{Days, Local, NaiveDate, TimeDelta, Timelike};
-use clap::{Arg, ArgAction, Command};
-use config::Config;
-use database::open_database_connection;
-use day::DayRepository;
-use eyre::{eyre, ContextCompat};
+use chrono::{Datelike, Days, Months, NaiveDate, TimeDelta};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use termfmt::{TermFmtExt, TermFmtsExt};
+use ttrace::{
+    check,
+    database::open_database_read_only,
+    day::Day,
+    export, import, open_database_connection,
+    report::{Report, ReportGrouping},
+    status::{self, Status},
+    summary::{Summary, WeekSummary},
+    task::Task,
+    Clock, Config, DayRepository, Error, JournalRepository, Profile, Result, TaskRepository,
+    TimeOrDelta,
+};
 
-use crate::task::TaskRepository;
-
-use self::output::{DataBundle, OutputFmt};
-use self::time::TimeOrDelta;
+use self::output::{Output, OutputFmt};
 
-mod config;
-mod database;
-mod day;
 mod output;
-mod task;
-mod time;
 
-fn main() -> eyre::Result<()> {
+/// Commands which change tasks, they are recorded in the journal. `delete`
+/// begins its operation only after the confirmation, as the operation holds
+/// the write lock of the database.
+const MUTATING_COMMANDS: &[&str] = &[
+    "start", "stop", "pause", "resume", "continue", "add", "note", "rename", "restart", "edit",
+    "split", "merge", "compact", "restore", "import", "check",
+];
+
+fn main() {
     let cli = Command::new("ttrack")
         .subcommands([
             Command::new("start")
@@ -37,13 +47,84 @@ fn main() -> eyre::Result<()> {
                         .short('t')
                         .num_args(1..)
                         .help("tags of the task (may be used to associate projects)"),
+                    Arg::new("at")
+                        .long("at")
+                        .num_args(1)
+                        .allow_hyphen_values(true)
+                        .help("start time in the past, e.g. 1215 or -10, defaults to now"),
                 ])
                 .about("start a new task, if another task is running it will get stopped"),
-            Command::new("stop").about("stop the currently running task"),
+            Command::new("stop")
+                .arg(
+                    Arg::new("at")
+                        .long("at")
+                        .num_args(1)
+                        .allow_hyphen_values(true)
+                        .help("end time in the past, e.g. 1215 or -10, defaults to now"),
+                )
+                .about("stop the currently running task"),
+            Command::new("pause").about("stop the currently running task, so that it can be resumed"),
+            Command::new("resume")
+                .about("start a new task like the paused one or the one which ended last, with the same description and tags"),
+            Command::new("continue")
+                .arg(
+                    Arg::new("id")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("id of the task, defaults to the task which ended last"),
+                )
+                .about("start a new task like an earlier one, the running task gets stopped"),
+            Command::new("add")
+                .args([
+                    Arg::new("description")
+                        .num_args(1)
+                        .required(true)
+                        .help("name of the task"),
+                    Arg::new("start")
+                        .num_args(1)
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .help("start time of the task"),
+                    Arg::new("end")
+                        .num_args(1)
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .help("end time of the task"),
+                    Arg::new("date")
+                        .long("date")
+                        .short('d')
+                        .num_args(1)
+                        .help("date of the task (YYYY.MM.DD), defaults to today"),
+                    Arg::new("tags")
+                        .long("tags")
+                        .short('t')
+                        .num_args(1..)
+                        .help("tags of the task (may be used to associate projects)"),
+                    Arg::new("adjust")
+                        .long("adjust")
+                        .short('a')
+                        .action(ArgAction::SetTrue)
+                        .help("trim overlapping neighbour tasks instead of failing"),
+                ])
+                .about("add a finished task retroactively"),
+            Command::new("note")
+                .args([
+                    Arg::new("note")
+                        .num_args(1)
+                        .required(true)
+                        .help("line to append to the notes"),
+                    Arg::new("id")
+                        .long("id")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("id of the task, defaults to the running task"),
+                ])
+                .about("append a note to the running task"),
             Command::new("rename")
                 .arg(
                     Arg::new("description")
                         .num_args(1)
+                        .required(true)
                         .help("the new name of the currently running task"),
                 )
                 .about("rename the current task."),
@@ -52,12 +133,17 @@ fn main() -> eyre::Result<()> {
                     Arg::new("time")
                         .num_args(1)
                         .required(true)
-                        .allow_negative_numbers(true)
-                        .help("the new start time of the currently running task"),
+                        .allow_hyphen_values(true)
+                        .help("the new start time of the currently running task, e.g. 10:30 or -15m"),
                 )
-                .about("rename the current task."),
+                .about("change the start of the currently running task"),
             Command::new("edit")
                 .args([
+                    Arg::new("id")
+                        .long("id")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("id of the task to edit (defaults to the currently running task)"),
                     Arg::new("name")
                         .long("name")
                         .short('n')
@@ -67,9 +153,126 @@ fn main() -> eyre::Result<()> {
                         .short('t')
                         .num_args(1..)
                         .help("new tags of the task (replaces all old ones)"),
+                    Arg::new("start")
+                        .long("start")
+                        .short('s')
+                        .num_args(1)
+                        .allow_hyphen_values(true)
+                        .help("new start time of the task, e.g. 10:30, -15m or 'yesterday 17:00'"),
+                    Arg::new("end")
+                        .long("end")
+                        .short('e')
+                        .num_args(1)
+                        .allow_hyphen_values(true)
+                        .help("new end time of the task, e.g. 17:30, +1h or now"),
+                ])
+                .about("edit the currently running task or the task with the given id"),
+            Command::new("split")
+                .args([
+                    Arg::new("task")
+                        .num_args(1)
+                        .required(true)
+                        .help("id of the task or current for the running task"),
+                    Arg::new("time")
+                        .num_args(1)
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .help("time at which the task is split, e.g. 1100 or -30 before the end"),
+                    Arg::new("description")
+                        .num_args(1)
+                        .help("description of the second task, defaults to the same description"),
+                ])
+                .about("split a task into two, the second task keeps the tags"),
+            Command::new("merge")
+                .args([
+                    Arg::new("ids")
+                        .num_args(2..)
+                        .required(true)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("ids of the tasks to merge"),
+                    Arg::new("gaps")
+                        .long("gaps")
+                        .action(ArgAction::SetTrue)
+                        .help("absorb the gaps between the tasks"),
+                ])
+                .about("combine tasks into one, which lasts from the first start to the last end"),
+            Command::new("compact")
+                .args([
+                    Arg::new("day")
+                        .long("day")
+                        .num_args(1)
+                        .allow_negative_numbers(true)
+                        .default_value("0")
+                        .help("number of days to go back"),
+                    Arg::new("gaps")
+                        .long("gaps")
+                        .action(ArgAction::SetTrue)
+                        .help("also merge tasks separated by a gap"),
+                ])
+                .about("merge consecutive tasks of a day with the same description"),
+            Command::new("delete")
+                .args([
+                    Arg::new("ids")
+                        .num_args(1..)
+                        .required(true)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("ids of the tasks to delete"),
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .help("do not ask for confirmation"),
                 ])
-                .about("edit the currently running task"),
+                .about("delete tasks, they can be restored later"),
+            Command::new("restore")
+                .arg(
+                    Arg::new("ids")
+                        .num_args(1..)
+                        .required(true)
+                        .value_parser(clap::value_parser!(u64))
+                        .help("ids of the deleted tasks to restore"),
+                )
+                .about("restore deleted tasks"),
+            Command::new("deleted")
+                .arg(
+                    Arg::new("count")
+                        .num_args(1)
+                        .default_value("10")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("number of deleted tasks to list"),
+                )
+                .about("list the recently deleted tasks"),
+            Command::new("undo").about("undo the last command which changed tasks"),
+            Command::new("redo").about("redo the last undone command"),
+            Command::new("history")
+                .arg(
+                    Arg::new("count")
+                        .num_args(1)
+                        .default_value("10")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("number of operations to list"),
+                )
+                .about("list the recent commands which changed tasks"),
             Command::new("get").about("get the currently running task"),
+            Command::new("status")
+                .args([
+                    Arg::new("template")
+                        .long("format")
+                        .num_args(1)
+                        .default_value(status::DEFAULT_TEMPLATE)
+                        .help("template with {id}, {description}, {tags}, {start}, {elapsed} and {day_total}"),
+                    Arg::new("idle")
+                        .long("idle")
+                        .num_args(1)
+                        .default_value("")
+                        .help("template used while no task is running"),
+                    Arg::new("bar")
+                        .long("bar")
+                        .num_args(1)
+                        .value_parser(["waybar", "i3blocks"])
+                        .help("print the json of a status bar module"),
+                ])
+                .about("print a single line about the running task for prompts and status bars"),
             Command::new("today").about("list the tasks of today"),
             Command::new("yesterday").about("list the task of yesterday"),
             Command::new("day")
@@ -85,7 +288,8 @@ fn main() -> eyre::Result<()> {
                 .arg(
                     Arg::new("date")
                         .num_args(1)
-                        .help("number of days to go back"),
+                        .required(true)
+                        .help("the date to list (YYYY.MM.DD)"),
                 )
                 .about("list the task of on the date"),
             Command::new("week")
@@ -97,137 +301,771 @@ fn main() -> eyre::Result<()> {
                         .help("number of weeks to go back"),
                 )
                 .about("list the task of the week"),
+            Command::new("month")
+                .arg(
+                    Arg::new("months")
+                        .num_args(1)
+                        .allow_negative_numbers(true)
+                        .default_value("0")
+                        .help("number of months to go back"),
+                )
+                .about("summarize the days and weeks of the month"),
+            Command::new("year")
+                .arg(
+                    Arg::new("years")
+                        .num_args(1)
+                        .allow_negative_numbers(true)
+                        .default_value("0")
+                        .help("number of years to go back"),
+                )
+                .about("summarize the weeks of the year"),
+            Command::new("report")
+                .args([
+                    Arg::new("from").long("from").num_args(1).help(
+                        "first date of the report (YYYY.MM.DD), defaults to the start of the month",
+                    ),
+                    Arg::new("to")
+                        .long("to")
+                        .num_args(1)
+                        .help("last date of the report (YYYY.MM.DD), defaults to today"),
+                    Arg::new("by")
+                        .long("by")
+                        .num_args(1)
+                        .default_value("tag")
+                        .value_parser(["tag", "description", "day", "week"])
+                        .help("how the tasks are grouped"),
+                ])
+                .about("aggregate the time spent over a range of days"),
+            Command::new("export")
+                .args([
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .num_args(1)
+                        .default_value("csv")
+                        .value_parser(["csv"])
+                        .help("format of the export"),
+                    Arg::new("from").long("from").num_args(1).help(
+                        "first date of the export (YYYY.MM.DD), defaults to the start of the month",
+                    ),
+                    Arg::new("to")
+                        .long("to")
+                        .num_args(1)
+                        .help("last date of the export (YYYY.MM.DD), defaults to today"),
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .num_args(1)
+                        .help("file to write the export to, defaults to stdout"),
+                ])
+                .about("export the tasks of a range of days, e.g. for timesheets"),
+            Command::new("import")
+                .args([
+                    Arg::new("from")
+                        .long("from")
+                        .num_args(1)
+                        .required(true)
+                        .value_parser(["timewarrior", "toggl"])
+                        .help("application which created the export"),
+                    Arg::new("file")
+                        .num_args(1)
+                        .required(true)
+                        .help("the json export of timewarrior or the detailed csv report of toggl"),
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("only show what would be imported"),
+                ])
+                .about("import tasks from other time tracking applications"),
+            Command::new("check")
+                .arg(
+                    Arg::new("fix")
+                        .long("fix")
+                        .action(ArgAction::SetTrue)
+                        .help("repair the problems, the changes can be undone"),
+                )
+                .about("find overlapping tasks, multiple running tasks and other problems"),
             Command::new("is_active").about("exit successfully if a task is currently running"),
+            Command::new("config")
+                .subcommands([
+                    Command::new("show").about("show the current settings"),
+                    Command::new("path").about("show the path of the config file"),
+                    Command::new("set")
+                        .args([
+                            Arg::new("key")
+                                .num_args(1)
+                                .required(true)
+                                .help("name of the setting, e.g. week_start or rounding.increment"),
+                            Arg::new("value")
+                                .num_args(1)
+                                .required(true)
+                                .help("new value, lists are separated by commas"),
+                        ])
+                        .about("change a setting in the config file"),
+                ])
+                .subcommand_required(true)
+                .about("show or change the settings"),
+            Command::new("profile")
+                .subcommands([
+                    Command::new("list")
+                        .about("list the profiles with their currently running task"),
+                    Command::new("create")
+                        .arg(
+                            Arg::new("name")
+                                .num_args(1)
+                                .required(true)
+                                .help("name of the profile"),
+                        )
+                        .about("create a profile with its own database and settings"),
+                    Command::new("switch")
+                        .arg(
+                            Arg::new("name")
+                                .num_args(1)
+                                .required(true)
+                                .help("name of the profile"),
+                        )
+                        .about("use the profile for the following commands"),
+                ])
+                .subcommand_required(true)
+                .about("manage profiles, e.g. to keep the tasks of employers apart"),
         ])
         .about("track the time you spend on projects or other tasks")
         .subcommand_required(true)
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .short('p')
+                .num_args(1)
+                .global(true)
+                .help("profile to use instead of the one switched to"),
+        )
         .termfmts()
         .get_matches();
 
-    let config = Config::load()?;
-    let connection = open_database_connection(&config)?;
+    let profile = Profile::current(cli.get_one::<String>("profile").map(String::as_str));
+    let config = match &profile {
+        Ok(profile) => profile.config(),
+        Err(_) => Ok(Config::default()),
+    };
+    let default_config = Config::default();
+    let term_config = config.as_ref().unwrap_or(&default_config);
+    let mut term = Output::new(cli.termfmt(term_config), term_config);
+    let result = match cli.subcommand() {
+        Some(("profile", command)) => run_profile(command, profile, config, &mut term),
+        Some(("status", command)) => profile
+            .and(config)
+            .and_then(|config| run_status(command, &config, &mut term)),
+        Some(("config", command)) => {
+            profile.and_then(|profile| run_config(command, &profile, config, &mut term))
+        }
+        _ => profile
+            .and(config)
+            .and_then(|config| run(&cli, &config, &mut term)),
+    };
+    let code = match result {
+        Ok(()) => 0,
+        Err(error) => {
+            term.error(&error);
+            error.exit_code()
+        }
+    };
+    term.flush();
+    term.end();
+    exit(code);
+}
+
+fn run(cli: &ArgMatches, config: &Config, term: &mut Output) -> Result<()> {
+    let clock = Clock::from_env()?;
+    let connection = open_database_connection(config, clock)?;
 
-    let mut term = cli.termfmt(&config);
+    let day_repository = DayRepository::new(connection.clone(), clock);
+    let journal_repository = Rc::new(JournalRepository::new(connection.clone(), clock));
+    let task_repository = TaskRepository::new(connection, journal_repository.clone(), clock)
+        .with_rounding(config.rounding());
 
-    let day_repository = DayRepository::new(connection.clone())?;
-    let task_repository = TaskRepository::new(connection)?;
+    let (name, _) = cli.subcommand().unwrap();
+    if MUTATING_COMMANDS.contains(&name) {
+        journal_repository.begin(&command_line())?;
+    }
 
     match cli.subcommand().unwrap() {
         ("start", command) => {
             let description: &String = command.get_one("description").unwrap();
+            let tags = tags_or_default(command, config);
+            let task = match command.get_one::<String>("at") {
+                Some(time) => {
+                    let time = TimeOrDelta::from_str(time)?.datetime(clock.today(), clock.now());
+                    let day = day_repository.from_date(time.date())?;
+                    task_repository.start_at(day, time, description, &tags)?
+                }
+                None => {
+                    let today = day_repository.today()?;
+                    task_repository.start(today, description, &tags)?
+                }
+            };
+            term.task(&task);
+        }
+        ("add", command) => {
+            let description: &String = command.get_one("description").unwrap();
+            let start: &String = command.get_one("start").unwrap();
+            let end: &String = command.get_one("end").unwrap();
+            let tags = tags_or_default(command, config);
+            let date = match command.get_one::<String>("date") {
+                Some(date) => parse_date(date)?,
+                None => clock.today(),
+            };
+            let start = TimeOrDelta::from_str(start)?.datetime(date, clock.now());
+            let end = TimeOrDelta::from_str(end)?;
+            let end = match end.datetime(date, clock.now()) {
+                time if time <= start && matches!(end, TimeOrDelta::Time(_)) => {
+                    time + TimeDelta::days(1)
+                }
+                time => time,
+            };
+            if command.get_flag("adjust") {
+                task_repository.make_room(start, end)?;
+            }
+            let day = day_repository.from_date(start.date())?;
+            let task = task_repository.add(day, start, end, description, &tags)?;
+            term.task(&task);
+        }
+        ("stop", command) => {
+            let task = match command.get_one::<String>("at") {
+                Some(time) => {
+                    let time = TimeOrDelta::from_str(time)?;
+                    let current = task_repository.current()?;
+                    task_repository.stop_at(time.end_of(&current, clock.now()))?
+                }
+                None => task_repository.stop()?,
+            };
+            term.task(&task);
+        }
+        ("pause", _) => {
+            let task = task_repository.pause()?;
+            term.task(&task);
+        }
+        ("resume", _) => {
             let today = day_repository.today()?;
-            let task = task_repository.start(today, description.as_str())?;
+            let task = task_repository.resume(today)?;
             term.task(&task);
         }
-        ("stop", _) => {
+        ("continue", command) => {
+            let earlier = match command.get_one::<u64>("id") {
+                Some(id) => task_repository.resolve(task_repository.task(*id)?)?,
+                None => task_repository.last()?,
+            };
             let today = day_repository.today()?;
-            let Ok(task) = task_repository.stop(today) else {
-                term.error("no task is started yet!");
-                term.end();
-                return Ok(());
+            let task = task_repository.continue_task(today, &earlier)?;
+            term.task(&task);
+        }
+        ("note", command) => {
+            let note: &String = command.get_one("note").unwrap();
+            let task = match command.get_one::<u64>("id") {
+                Some(id) => task_repository.resolve(task_repository.task(*id)?)?,
+                None => task_repository.current()?,
             };
+            let task = task_repository.add_note(task, note)?;
             term.task(&task);
         }
         ("rename", command) => {
             let description: &String = command.get_one("description").unwrap();
-            let today = day_repository.today()?;
-            let task = task_repository.rename_current(today, description)?;
+            let task = task_repository.rename_current(description)?;
             term.task(&task);
         }
         ("restart", command) => {
             let time: &String = command.get_one("time").unwrap();
-            let time_or_delta = TimeOrDelta::from_str(&time)?;
-            let day = day_repository.today()?;
-            let task = task_repository.current(day)?;
-            let task = match time_or_delta {
-                TimeOrDelta::Time(time) => task_repository.set_start(task, time),
-                TimeOrDelta::Delta(delta) => task_repository.shift_start(task, delta),
-            }?;
+            let time = TimeOrDelta::from_str(time)?;
+            let task = task_repository.current()?;
+            let start = time.start_of(&task, clock.now());
+            let task = task_repository.set_start(task, start)?;
             term.task(&task);
         }
-        ("today", _) => {
-            let Ok(today) = day_repository.today() else {
-                term.error("could not find or create day!");
-                term.end();
-                return Ok(());
+        ("edit", command) => {
+            let mut task = match command.get_one::<u64>("id") {
+                Some(id) => task_repository.resolve(task_repository.task(*id)?)?,
+                None => task_repository.current()?,
+            };
+            let start = match command.get_one::<String>("start") {
+                Some(time) => Some(TimeOrDelta::from_str(time)?.start_of(&task, clock.now())),
+                None => None,
+            };
+            let end = match command.get_one::<String>("end") {
+                Some(time) => {
+                    let start = start.unwrap_or(task.start());
+                    Some(TimeOrDelta::from_str(time)?.end_after(&task, start, clock.now()))
+                }
+                None => None,
+            };
+            task = match (start, end) {
+                (Some(start), Some(end)) => task_repository.set_range(task, start, end)?,
+                (Some(start), None) => task_repository.set_start(task, start)?,
+                (None, Some(end)) => task_repository.set_end(task, end)?,
+                (None, None) => task,
             };
+            if let Some(description) = command.get_one::<String>("name") {
+                task = task_repository.rename_task(task, description)?;
+            }
+            if let Some(tags) = command.get_many::<String>("tags") {
+                let tags: Vec<String> = tags.cloned().collect();
+                task = task_repository.set_tags(task, &tags)?;
+            }
+            term.task(&task);
+        }
+        ("split", command) => {
+            let task: &String = command.get_one("task").unwrap();
+            let task = match task.as_str() {
+                "current" => task_repository.current()?,
+                id => task_repository.resolve(task_repository.task(parse_id(id)?)?)?,
+            };
+            let time: &String = command.get_one("time").unwrap();
+            let time = TimeOrDelta::from_str(time)?.end_of(&task, clock.now());
+            let day = day_repository.from_date(time.date())?;
+            let description = command.get_one::<String>("description");
+            let (first, second) =
+                task_repository.split(task, day, time, description.map(String::as_str))?;
+            term.task(&first);
+            term.task(&second);
+        }
+        ("merge", command) => {
+            let tasks = command
+                .get_many::<u64>("ids")
+                .unwrap()
+                .map(|id| task_repository.resolve(task_repository.task(*id)?))
+                .collect::<Result<Vec<_>>>()?;
+            let task = task_repository.merge(tasks, command.get_flag("gaps"))?;
+            term.task(&task);
+        }
+        ("compact", command) => {
+            let days: &String = command.get_one("day").unwrap();
+            let day = day_repository.from_date(days_ago(days, clock)?)?;
+            let tasks = task_repository.compact(day, command.get_flag("gaps"))?;
+            if tasks.is_empty() {
+                term.info("there is nothing to compact");
+            }
+            for task in tasks.iter() {
+                term.task(task);
+            }
+        }
+        ("delete", command) => {
+            let tasks = command
+                .get_many::<u64>("ids")
+                .unwrap()
+                .map(|id| task_repository.resolve(task_repository.task(*id)?))
+                .collect::<Result<Vec<_>>>()?;
+            for task in tasks.iter() {
+                term.task(task);
+            }
+            if command.get_flag("yes")
+                || term.confirm(format_args!("delete {} task(s)?", tasks.len()))?
+            {
+                journal_repository.begin(&command_line())?;
+                for task in tasks {
+                    task_repository.delete(task)?;
+                }
+            }
+        }
+        ("restore", command) => {
+            for id in command.get_many::<u64>("ids").unwrap() {
+                let task = task_repository.resolve(task_repository.deleted_task(*id)?)?;
+                let task = task_repository.restore(task)?;
+                term.task(&task);
+            }
+        }
+        ("deleted", command) => {
+            let count: &usize = command.get_one("count").unwrap();
+            for task in task_repository.deleted_tasks(*count)? {
+                term.task(&task);
+            }
+        }
+        ("today", _) => {
+            let today = day_repository.today()?;
             let tasks_for_day = task_repository.day_with_tasks(today)?;
             term.day_with_tasks(&tasks_for_day);
         }
         ("yesterday", _) => {
-            let Ok(yesterday) = day_repository.yesterday().map_err(|error| {
-                term.error(format_args!("could not find or create day: {}", error));
-                term.end();
-            }) else {
-                return Ok(());
-            };
+            let yesterday = day_repository.yesterday()?;
             let tasks_for_day = task_repository.day_with_tasks(yesterday)?;
             term.day_with_tasks(&tasks_for_day);
         }
         ("date", command) => {
             let date: &String = command.get_one("date").unwrap();
-            let date = NaiveDate::parse_from_str(date, "%Y.%m.%d")?;
+            let date = parse_date(date)?;
             let day = day_repository.from_date(date)?;
             let day_with_tasks = task_repository.day_with_tasks(day)?;
             term.day_with_tasks_txt(&day_with_tasks);
         }
         ("day", command) => {
             let days: &String = command.get_one("days").unwrap();
-            let days = i32::from_str(days)?;
-            let date = Local::now()
-                .date_naive()
-                .checked_sub_days(Days::new(days.abs() as u64))
-                .wrap_err("cannot sub days")?;
-            let day = day_repository.from_date(date)?;
+            let day = day_repository.from_date(days_ago(days, clock)?)?;
             let day_with_tasks = task_repository.day_with_tasks(day)?;
             term.day_with_tasks(&day_with_tasks);
         }
         ("week", command) => {
             let weeks: &String = command.get_one("weeks").unwrap();
-            let weeks = i32::from_str(weeks)?;
+            let weeks = parse_number(weeks)?;
             let week = if weeks == 0 {
-                day_repository.week_till_today()?
+                day_repository.week_till_today(config.week_start())?
             } else {
-                let weeks = weeks.abs() as u64;
-                let today = Local::now().date_naive();
+                let weeks = weeks.unsigned_abs() as u64;
+                let today = clock.today();
                 let date = today
                     .checked_sub_days(Days::new(weeks * 7))
                     .unwrap_or(today);
-                day_repository.complete_week(date)?
+                day_repository.complete_week(date, config.week_start())?
             };
-            let week = week
+            let week: Vec<_> = week
                 .into_iter()
-                .filter_map(|day| task_repository.day_with_tasks(day).ok());
-            for day_with_tasks in week {
-                term.day_with_tasks(&day_with_tasks);
+                .filter_map(|day| task_repository.day_with_tasks(day).ok())
+                .collect();
+            for day_with_tasks in week.iter() {
+                term.day_with_tasks(day_with_tasks);
+            }
+            if let Some(week) = WeekSummary::from_days(&week, config.week_start()) {
+                term.week_summary(&week);
             }
         }
-        ("get", _) => {
-            let Ok(today) = day_repository.today() else {
-                term.error("could not get todays day!");
-                term.end();
-                return Ok(());
-            };
-            if let Ok(task) = task_repository.current(today) {
-                term.task(&task);
+        ("month", command) => {
+            let months: &String = command.get_one("months").unwrap();
+            let date = months_ago(parse_number(months)?.unsigned_abs(), clock)?;
+            let days = day_repository
+                .month(date)?
+                .into_iter()
+                .map(|day| task_repository.day_with_tasks(day))
+                .collect::<ttrace::Result<Vec<_>>>()?;
+            if let (Some(first), Some(last)) = (days.first(), days.last()) {
+                let summary = Summary::new(
+                    first.day().date(),
+                    last.day().date(),
+                    &days,
+                    config.week_start(),
+                );
+                term.summary(&summary, true);
+            }
+        }
+        ("year", command) => {
+            let years: &String = command.get_one("years").unwrap();
+            let months = parse_number(years)?
+                .unsigned_abs()
+                .checked_mul(12)
+                .ok_or_else(|| Error::InvalidInput("cannot subtract the years".to_owned()))?;
+            let date = months_ago(months, clock)?;
+            let days = day_repository
+                .year(date)?
+                .into_iter()
+                .map(|day| task_repository.day_with_tasks(day))
+                .collect::<ttrace::Result<Vec<_>>>()?;
+            if let (Some(first), Some(last)) = (days.first(), days.last()) {
+                let summary = Summary::new(
+                    first.day().date(),
+                    last.day().date(),
+                    &days,
+                    config.week_start(),
+                );
+                term.summary(&summary, false);
+            }
+        }
+        ("report", command) => {
+            let (from, to) = date_range(command, clock)?;
+            let by: &String = command.get_one("by").unwrap();
+            let by = ReportGrouping::from_str(by)?;
+            let days = day_repository
+                .range(from, to)?
+                .into_iter()
+                .map(|day| task_repository.day_with_tasks(day))
+                .collect::<ttrace::Result<Vec<_>>>()?;
+            let report = Report::new(from, to, by, &days, config.rounding(), config.week_start());
+            term.report(&report);
+        }
+        ("export", command) => {
+            let (from, to) = date_range(command, clock)?;
+            let days = day_repository
+                .range(from, to)?
+                .into_iter()
+                .map(|day| task_repository.day_with_tasks(day))
+                .collect::<ttrace::Result<Vec<_>>>()?;
+            match command.get_one::<String>("output") {
+                Some(path) => {
+                    export::write_csv(File::create(path)?, &days)?;
+                    term.info(format!("exported the tasks to {}", path));
+                }
+                // The csv would be mixed with the json or interactive output.
+                None if cli.get_flag("json") || cli.get_flag("interactive") => {
+                    return Err(Error::InvalidInput(
+                        "the export is written to stdout, use --output with --json or --interactive"
+                            .to_owned(),
+                    ));
+                }
+                None => export::write_csv(stdout().lock(), &days)?,
+            }
+        }
+        ("import", command) => {
+            let from: &String = command.get_one("from").unwrap();
+            let file: &String = command.get_one("file").unwrap();
+            let file = File::open(file)
+                .map_err(|error| Error::Import(format!("could not open {}: {}", file, error)))?;
+            let entries = match from.as_str() {
+                "timewarrior" => import::parse_timewarrior(file),
+                _ => import::parse_toggl(file),
+            }?;
+            let dry_run = command.get_flag("dry-run");
+            let summary = import::import(entries, dry_run, &day_repository, &task_repository)?;
+            if dry_run {
+                journal_repository.rollback()?;
+            }
+            term.import_summary(&summary);
+        }
+        ("get", _) => match task_repository.current() {
+            Ok(task) => term.task(&task),
+            Err(Error::NoActiveTask) => {}
+            Err(error) => return Err(error),
+        },
+        ("check", command) => {
+            let fix = command.get_flag("fix");
+            let issues = check::check(&day_repository, &task_repository, fix)?;
+            if issues.is_empty() {
+                term.info("no problems found");
+            }
+            for issue in issues.iter() {
+                term.issue(issue);
+            }
+            let unfixed = issues.iter().filter(|issue| !issue.is_fixed()).count();
+            if unfixed > 0 {
+                journal_repository.commit()?;
+                let hint = match fix {
+                    true => "they have to be repaired manually",
+                    false => "run ttrace check --fix to repair them",
+                };
+                return Err(Error::Inconsistent(format!(
+                    "found {} problem(s), {}",
+                    unfixed, hint
+                )));
             }
         }
         ("is_active", _) => {
-            let Ok(today) = day_repository.today() else {
-                term.error("could not get todays day!");
-                term.end();
-                exit(1);
+            let task = task_repository.current()?;
+            term.task(&task);
+        }
+        ("undo", _) => match journal_repository.undo()? {
+            Some(operation) => term.operation(&operation),
+            None => term.info("there is nothing to undo"),
+        },
+        ("redo", _) => match journal_repository.redo()? {
+            Some(operation) => term.operation(&operation),
+            None => term.info("there is nothing to redo"),
+        },
+        ("history", command) => {
+            let count: &usize = command.get_one("count").unwrap();
+            for operation in journal_repository.history(*count)? {
+                term.operation(&operation);
+            }
+        }
+        (command, _) => {
+            return Err(Error::InvalidInput(format!(
+                "the command {} is not implemented",
+                command
+            )))
+        }
+    }
+
+    journal_repository.commit()
+}
+
+/// Reads the database without creating or migrating it, as status bars call
+/// the command every few seconds.
+fn run_status(command: &ArgMatches, config: &Config, term: &mut Output) -> Result<()> {
+    let clock = Clock::from_env()?;
+    let status = match open_database_read_only(config, clock)? {
+        Some(connection) => {
+            let day_repository = DayRepository::new(connection.clone(), clock);
+            let journal_repository = Rc::new(JournalRepository::new(connection.clone(), clock));
+            let task_repository = TaskRepository::new(connection, journal_repository, clock)
+                .with_rounding(config.rounding());
+            let task = match task_repository.current() {
+                Ok(task) => Some(task),
+                Err(Error::NoActiveTask) => None,
+                Err(error) => return Err(error),
             };
-            let Ok(task) = task_repository.current(today) else {
-                term.error("no task is currently active");
-                exit(1);
+            let today = match day_repository.find(clock.today())? {
+                Some(day) => Some(task_repository.day_with_tasks(day)?),
+                None => None,
             };
-            term.task(&task);
+            Status::new(task, today.as_ref())
+        }
+        None => Status::new(None, None),
+    };
+    let template: &String = match status.is_active() {
+        true => command.get_one("template").unwrap(),
+        false => command.get_one("idle").unwrap(),
+    };
+    let text = status.render(template, config.time_format())?;
+    let line = match command.get_one::<String>("bar").map(String::as_str) {
+        Some("waybar") => status.waybar(text, config.time_format()).to_string(),
+        Some(_) => status.i3blocks(text).to_string(),
+        None => text,
+    };
+    term.status(&status, &line);
+    Ok(())
+}
+
+fn run_config(
+    command: &ArgMatches,
+    profile: &Profile,
+    config: Result<Config>,
+    term: &mut Output,
+) -> Result<()> {
+    match command.subcommand().unwrap() {
+        ("show", _) => term.config(&config?),
+        ("path", _) => term.info(profile.config_path().display()),
+        ("set", command) => {
+            let key: &String = command.get_one("key").unwrap();
+            let value: &String = command.get_one("value").unwrap();
+            let path = profile.config_path();
+            let mut config = match path.exists() {
+                true => Config::read(&path)?,
+                false => Config::default(),
+            };
+            config.set(key, value)?;
+            config.write(&path)?;
+            term.config(&config);
+        }
+        (command, _) => {
+            return Err(Error::InvalidInput(format!(
+                "the command config {} is not implemented",
+                command
+            )))
         }
-        (command, _) => term.error(eyre!("the command {} is not implemented.", command)),
     }
+    Ok(())
+}
 
-    term.flush();
-    term.end();
+fn run_profile(
+    command: &ArgMatches,
+    current: Result<Profile>,
+    config: Result<Config>,
+    term: &mut Output,
+) -> Result<()> {
+    match command.subcommand().unwrap() {
+        ("list", _) => {
+            let clock = Clock::from_env()?;
+            let current = current.ok();
+            for profile in Profile::list()? {
+                let selected = current.as_ref() == Some(&profile);
+                match profile_task(&profile, clock) {
+                    Ok(task) => term.profile(&profile, selected, task.as_ref()),
+                    Err(error) => term.profile_error(&profile, selected, &error),
+                }
+            }
+        }
+        ("create", command) => {
+            let name: &String = command.get_one("name").unwrap();
+            let profile = Profile::create(name, &config?)?;
+            term.profile(&profile, false, None);
+        }
+        ("switch", command) => {
+            let name: &String = command.get_one("name").unwrap();
+            let profile = Profile::new(name)?;
+            profile.switch()?;
+            term.profile(&profile, true, None);
+        }
+        (command, _) => {
+            return Err(Error::InvalidInput(format!(
+                "the command profile {} is not implemented",
+                command
+            )))
+        }
+    }
     Ok(())
 }
+
+/// The running task of a profile, the database is neither created nor changed.
+fn profile_task(profile: &Profile, clock: Clock) -> Result<Option<Task<Day>>> {
+    let config = profile.config()?;
+    let Some(connection) = open_database_read_only(&config, clock)? else {
+        return Ok(None);
+    };
+    let journal_repository = Rc::new(JournalRepository::new(connection.clone(), clock));
+    let task_repository =
+        TaskRepository::new(connection, journal_repository, clock).with_rounding(config.rounding());
+    match task_repository.current() {
+        Ok(task) => Ok(Some(task)),
+        Err(Error::NoActiveTask) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// The tags given on the command line or the default tags of the config.
+fn tags_or_default(command: &ArgMatches, config: &Config) -> Vec<String> {
+    match command.get_many::<String>("tags") {
+        Some(tags) => tags.cloned().collect(),
+        None => config.default_tags().to_vec(),
+    }
+}
+
+fn command_line() -> String {
+    env::args()
+        .skip(1)
+        .map(|arg| match arg.contains(char::is_whitespace) {
+            true => format!("\"{}\"", arg),
+            false => arg,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The `--from` and `--to` dates, which default to the current month.
+fn date_range(command: &ArgMatches, clock: Clock) -> Result<(NaiveDate, NaiveDate)> {
+    let today = clock.today();
+    let from = match command.get_one::<String>("from") {
+        Some(date) => parse_date(date)?,
+        None => today.with_day(1).unwrap_or(today),
+    };
+    let to = match command.get_one::<String>("to") {
+        Some(date) => parse_date(date)?,
+        None => today,
+    };
+    if from > to {
+        return Err(Error::InvalidInput(format!(
+            "the range from {} to {} ends before it starts",
+            from.format("%Y.%m.%d"),
+            to.format("%Y.%m.%d")
+        )));
+    }
+    Ok((from, to))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y.%m.%d").map_err(|error| {
+        Error::InvalidInput(format!(
+            "invalid date '{}', expected YYYY.MM.DD: {}",
+            date, error
+        ))
+    })
+}
+
+/// The date a number of days before today, the sign of the number is ignored.
+fn days_ago(days: &str, clock: Clock) -> Result<NaiveDate> {
+    let days = parse_number(days)?;
+    clock
+        .today()
+        .checked_sub_days(Days::new(days.unsigned_abs() as u64))
+        .ok_or_else(|| Error::InvalidInput("cannot subtract the days".to_owned()))
+}
+
+/// The date a number of months before today.
+fn months_ago(months: u32, clock: Clock) -> Result<NaiveDate> {
+    clock
+        .today()
+        .checked_sub_months(Months::new(months))
+        .ok_or_else(|| Error::InvalidInput("cannot subtract the months".to_owned()))
+}
+
+fn parse_number(number: &str) -> Result<i32> {
+    i32::from_str(number)
+        .map_err(|error| Error::InvalidInput(format!("invalid number '{}': {}", number, error)))
+}
+
+fn parse_id(id: &str) -> Result<u64> {
+    u64::from_str(id).map_err(|_| {
+        Error::InvalidInput(format!("invalid task '{}', expected an id or current", id))
+    })
+}
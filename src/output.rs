@@ -1,20 +1,29 @@
-use core::task;
 use std::{
     fmt::Display,
-    io::{stdout, IsTerminal},
+    io::{stdin, stdout, Write},
+    path::PathBuf,
 };
 
+use chrono::{NaiveDateTime, TimeDelta};
 use serde::Serialize;
 use termfmt::{
-    chrono::{DateFmt, DeltaFmt, DeltaHourMinuteFmt, TimeEditFmt, TimeFmt},
-    termarrow, termarrow_fg, termerr, termh1, termh2, termprefix1, termprefix2, BundleFmt, Fg,
-    TermFmt, TermStyle,
+    chrono::{DateFmt, DeltaFmt, DeltaHourMinuteFmt, TimeEditFmt},
+    termarrow, termarrow_fg, termerr, terminfo, termprefix1, termprefix2, BundleFmt, Fg, TermFmt,
+    TermStyle,
 };
 
-use crate::{
+use ttrace::{
+    check::Issue,
     config::Config,
     day::Day,
-    task::{DayWithTasks, Task, TaskGroup},
+    import::ImportSummary,
+    journal::Operation,
+    profile::Profile,
+    report::Report,
+    status::Status,
+    summary::{Summary, WeekSummary},
+    task::{DayWithTasks, Task},
+    Error, Result,
 };
 
 #[derive(Default, Serialize)]
@@ -25,38 +34,125 @@ pub struct DataBundle {
     #[serde(rename = "output", skip_serializing_if = "Vec::is_empty")]
     day_with_tasks: Vec<DayWithTasks>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
+    operations: Vec<Operation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    reports: Vec<Report>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    imports: Vec<ImportSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    summaries: Vec<Summary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    weeks: Vec<WeekSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    profiles: Vec<ProfileEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<StatusEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    issues: Vec<Issue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     info: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    error: Vec<String>,
+    error: Vec<ErrorEntry>,
+}
+
+#[derive(Serialize)]
+struct ProfileEntry {
+    name: String,
+    selected: bool,
+    config_path: PathBuf,
+    task: Option<Task<Day>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct StatusEntry {
+    text: String,
+    #[serde(flatten)]
+    status: Status,
+}
+
+#[derive(Serialize)]
+struct ErrorEntry {
+    kind: &'static str,
+    code: i32,
+    message: String,
+}
+
+/// The output of the commands in the format chosen on the command line.
+pub struct Output {
+    term: TermFmt<DataBundle>,
+    time_format: String,
+}
+
+impl Output {
+    pub fn new(term: TermFmt<DataBundle>, config: &Config) -> Self {
+        Self {
+            term,
+            time_format: config.time_format().to_owned(),
+        }
+    }
+
+    pub fn flush(&mut self) {
+        _ = self.term.flush();
+    }
 }
 
 pub trait OutputFmt {
-    fn error(&mut self, value: impl Display);
+    fn error(&mut self, error: &Error);
+    fn info(&mut self, value: impl Display);
     fn day_with_tasks_txt(&mut self, value: &DayWithTasks);
     fn day_with_tasks(&mut self, value: &DayWithTasks);
     fn task(&mut self, task: &Task<Day>);
+    fn operation(&mut self, operation: &Operation);
+    fn report(&mut self, report: &Report);
+    fn import_summary(&mut self, summary: &ImportSummary);
+    fn summary(&mut self, summary: &Summary, with_days: bool);
+    fn week_summary(&mut self, week: &WeekSummary);
+    fn profile(&mut self, profile: &Profile, selected: bool, task: Option<&Task<Day>>);
+    fn profile_error(&mut self, profile: &Profile, selected: bool, error: &Error);
+    fn config(&mut self, config: &Config);
+    fn status(&mut self, status: &Status, line: &str);
+    fn issue(&mut self, issue: &Issue);
+    fn confirm(&mut self, question: impl Display) -> Result<bool>;
     fn end(&mut self);
 }
 
-impl OutputFmt for TermFmt<DataBundle> {
-    fn error(&mut self, value: impl Display) {
-        self.bundle(|bundle| bundle.error.push(format!("{}", value)));
-        if self.is_plain() {
-            println!("{}", value);
+impl OutputFmt for Output {
+    fn error(&mut self, error: &Error) {
+        self.term.bundle(|bundle| {
+            bundle.error.push(ErrorEntry {
+                kind: error.kind(),
+                code: error.exit_code(),
+                message: error.to_string(),
+            })
+        });
+        if self.term.is_plain() {
+            eprintln!("{}", error);
         }
-        if self.is_interactive() {
-            termerr(value);
+        if self.term.is_interactive() {
+            termerr(error);
+        }
+    }
+
+    fn info(&mut self, value: impl Display) {
+        self.term
+            .bundle(|bundle| bundle.info.push(value.to_string()));
+        self.term.plain(&value);
+        if self.term.is_interactive() {
+            terminfo(value);
         }
     }
 
     fn day_with_tasks_txt(&mut self, value: &DayWithTasks) {
-        self.bundle(|bundle| bundle.day_with_tasks.push(value.clone()));
-        if self.is_plain() || self.is_interactive() {
+        self.term
+            .bundle(|bundle| bundle.day_with_tasks.push(value.clone()));
+        if self.term.is_plain() || self.term.is_interactive() {
             for task in value.tasks() {
                 println!(
                     "{} {}",
-                    TimeEditFmt::new(task.start()),
-                    DeltaHourMinuteFmt::new(task.delta())
+                    TimeEditFmt::new(task.start().time()),
+                    DeltaHourMinuteFmt::new(task.delta_on(value.day()))
                 );
                 println!("{}", task.description());
                 println!()
@@ -65,20 +161,21 @@ impl OutputFmt for TermFmt<DataBundle> {
     }
 
     fn day_with_tasks(&mut self, value: &DayWithTasks) {
-        self.bundle(|bundle| bundle.day_with_tasks.push(value.clone()));
-        if self.is_plain() {
+        self.term
+            .bundle(|bundle| bundle.day_with_tasks.push(value.clone()));
+        if self.term.is_plain() {
             println!("{}", value.day());
             for task in value.tasks() {
                 println!("{}", task);
             }
         }
-        if self.is_interactive() {
+        if self.term.is_interactive() {
             termprefix1(
                 "Day",
                 format_args!(
                     "{} {}",
                     DateFmt::new(value.day().date()),
-                    format_args!("({})", DeltaFmt::new(value.delta())).fg_bright_black()
+                    RoundedFmt::new(value.delta(), value.raw_delta()).fg_bright_black()
                 ),
             );
             if value.is_empty() {
@@ -90,27 +187,335 @@ impl OutputFmt for TermFmt<DataBundle> {
                     format_args!(
                         "{} {}",
                         group.description(),
-                        format_args!("({})", DeltaFmt::new(group.delta())).fg_bright_black()
+                        RoundedFmt::new(group.delta(), group.raw_delta()).fg_bright_black()
                     ),
                 );
                 for task in group.tasks() {
-                    term_task_body(task);
+                    term_task_body(task, task.delta_on(group.day()), &self.time_format);
+                    term_task_notes(task);
                 }
             }
         }
     }
 
     fn task(&mut self, value: &Task<Day>) {
-        self.bundle(|bundle| bundle.tasks.push(value.clone()));
-        self.plain(value);
-        if self.is_interactive() {
+        self.term.bundle(|bundle| bundle.tasks.push(value.clone()));
+        self.term.plain(value);
+        if self.term.is_interactive() {
             termprefix2("Task", value.description());
-            term_task_body(&value);
+            term_task_body(value, value.delta(), &self.time_format);
+            term_task_notes(value);
+        }
+    }
+
+    fn operation(&mut self, value: &Operation) {
+        self.term
+            .bundle(|bundle| bundle.operations.push(value.clone()));
+        self.term.plain(value);
+        if self.term.is_interactive() {
+            termprefix2("Operation", value.command());
+            let state = match value.is_undone() {
+                true => " undone",
+                false => "",
+            };
+            termarrow(format_args!(
+                "{} {}{}",
+                value.created_at().format("%Y.%m.%d %H:%M"),
+                format_args!("({} changes)", value.changes()).fg_bright_black(),
+                state.fg_yellow()
+            ));
+        }
+    }
+
+    fn report(&mut self, value: &Report) {
+        self.term
+            .bundle(|bundle| bundle.reports.push(value.clone()));
+        if self.term.is_plain() {
+            println!("{}", value);
+            for group in value.groups() {
+                println!("{}", group);
+            }
+        }
+        if self.term.is_interactive() {
+            termprefix1(
+                "Report",
+                format_args!(
+                    "{} - {} by {} {}",
+                    value.from().format("%Y.%m.%d"),
+                    value.to().format("%Y.%m.%d"),
+                    value.by(),
+                    RoundedFmt::new(value.total(), value.raw_total()).fg_bright_black()
+                ),
+            );
+            if value.is_empty() {
+                termarrow("no tasks recorded!".fg_bright_black());
+            }
+            for group in value.groups() {
+                termarrow(format_args!(
+                    "{} {} {}",
+                    group.key(),
+                    DeltaFmt::new(group.delta()),
+                    format_args!(
+                        "({:.1}%{})",
+                        group.percentage(),
+                        RawFmt::new(group.delta(), group.raw_delta())
+                    )
+                    .fg_bright_black()
+                ));
+            }
+        }
+    }
+
+    fn import_summary(&mut self, value: &ImportSummary) {
+        self.term
+            .bundle(|bundle| bundle.imports.push(value.clone()));
+        if self.term.is_plain() {
+            println!("{}", value);
+            for task in value.imported() {
+                println!("{}", task);
+            }
+            for entry in value.duplicates() {
+                println!("duplicate {}", entry);
+            }
+            for skipped in value.skipped() {
+                println!(
+                    "skipped {} reason=\"{}\"",
+                    skipped.entry(),
+                    skipped.reason()
+                );
+            }
+        }
+        if self.term.is_interactive() {
+            let dry_run = match value.is_dry_run() {
+                true => " (dry run)",
+                false => "",
+            };
+            termprefix1(
+                "Import",
+                format_args!(
+                    "{} imported, {} duplicates, {} skipped{}",
+                    value.imported().count(),
+                    value.duplicates().count(),
+                    value.skipped().count(),
+                    dry_run.fg_yellow()
+                ),
+            );
+            for entry in value.duplicates() {
+                termarrow_fg(
+                    Fg::Yellow,
+                    format_args!(
+                        "{} {}",
+                        entry.description(),
+                        format_args!("({}) duplicate", entry.start().format("%Y.%m.%d %H:%M"))
+                            .fg_bright_black()
+                    ),
+                );
+            }
+            for skipped in value.skipped() {
+                termarrow_fg(
+                    Fg::Red,
+                    format_args!(
+                        "{} {}",
+                        skipped.entry().description(),
+                        format_args!(
+                            "({}) {}",
+                            skipped.entry().start().format("%Y.%m.%d %H:%M"),
+                            skipped.reason()
+                        )
+                        .fg_bright_black()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn summary(&mut self, value: &Summary, with_days: bool) {
+        self.term
+            .bundle(|bundle| bundle.summaries.push(value.clone()));
+        if self.term.is_plain() {
+            println!("{}", value);
+            for week in value.weeks() {
+                println!("{}", week);
+                if with_days {
+                    for day in week.days() {
+                        println!("{}", day);
+                    }
+                }
+            }
+        }
+        if self.term.is_interactive() {
+            termprefix1(
+                "Summary",
+                format_args!(
+                    "{} - {} {}",
+                    value.from().format("%Y.%m.%d"),
+                    value.to().format("%Y.%m.%d"),
+                    format_args!("({})", DeltaFmt::new(value.total())).fg_bright_black()
+                ),
+            );
+            for week in value.weeks() {
+                if !with_days {
+                    termarrow(format_args!(
+                        "{} {} {}",
+                        WeekFmt::new(week),
+                        DeltaFmt::new(week.total()),
+                        WeekRangeFmt::new(week).fg_bright_black()
+                    ));
+                    continue;
+                }
+                termprefix2(
+                    "Week",
+                    format_args!(
+                        "{} {}",
+                        WeekFmt::new(week),
+                        format_args!("({})", DeltaFmt::new(week.total())).fg_bright_black()
+                    ),
+                );
+                for day in week.days() {
+                    let color = match day.total().is_zero() {
+                        true => Fg::BrightBlack,
+                        false => Fg::Blue,
+                    };
+                    termarrow_fg(
+                        color,
+                        format_args!(
+                            "{} {}",
+                            day.date().format("%a %d.%m"),
+                            DeltaFmt::new(day.total())
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn week_summary(&mut self, value: &WeekSummary) {
+        self.term.bundle(|bundle| bundle.weeks.push(value.clone()));
+        self.term.plain(value);
+        if self.term.is_interactive() {
+            termprefix1(
+                "Week",
+                format_args!(
+                    "{} {} {}",
+                    WeekFmt::new(value),
+                    DeltaFmt::new(value.total()),
+                    WeekRangeFmt::new(value).fg_bright_black()
+                ),
+            );
+        }
+    }
+
+    fn profile(&mut self, value: &Profile, selected: bool, task: Option<&Task<Day>>) {
+        self.term.bundle(|bundle| {
+            bundle.profiles.push(ProfileEntry {
+                name: value.name().to_owned(),
+                selected,
+                config_path: value.config_path(),
+                task: task.cloned(),
+                error: None,
+            })
+        });
+        if self.term.is_plain() {
+            match selected {
+                true => println!("{} selected", value),
+                false => println!("{}", value),
+            }
+            if let Some(task) = task {
+                println!("{}", task);
+            }
+        }
+        if self.term.is_interactive() {
+            let selected = match selected {
+                true => "(selected)",
+                false => "",
+            };
+            termprefix1(
+                "Profile",
+                format_args!("{} {}", value.name(), selected.fg_bright_black()),
+            );
+            if let Some(task) = task {
+                termprefix2("Task", task.description());
+                term_task_body(task, task.delta(), &self.time_format);
+            }
+        }
+    }
+
+    fn profile_error(&mut self, value: &Profile, selected: bool, error: &Error) {
+        self.term.bundle(|bundle| {
+            bundle.profiles.push(ProfileEntry {
+                name: value.name().to_owned(),
+                selected,
+                config_path: value.config_path(),
+                task: None,
+                error: Some(error.to_string()),
+            })
+        });
+        if self.term.is_plain() {
+            println!("{} error=\"{}\"", value, error);
+        }
+        if self.term.is_interactive() {
+            termprefix1("Profile", value.name());
+            termarrow_fg(Fg::Red, error);
+        }
+    }
+
+    fn config(&mut self, value: &Config) {
+        self.term.bundle(|bundle| bundle.config = value.clone());
+        if self.term.is_plain() || self.term.is_interactive() {
+            match value.to_toml() {
+                Ok(content) => print!("{}", content),
+                Err(error) => self.error(&error),
+            }
+        }
+    }
+
+    fn status(&mut self, value: &Status, line: &str) {
+        self.term.bundle(|bundle| {
+            bundle.status = Some(StatusEntry {
+                text: line.to_owned(),
+                status: value.clone(),
+            })
+        });
+        if self.term.is_plain() || self.term.is_interactive() {
+            println!("{}", line);
+        }
+    }
+
+    fn issue(&mut self, value: &Issue) {
+        self.term.bundle(|bundle| bundle.issues.push(value.clone()));
+        self.term.plain(value);
+        if self.term.is_interactive() {
+            let (color, state) = match value.is_fixed() {
+                true => (Fg::Green, "fixed"),
+                false => (Fg::Red, "not fixed"),
+            };
+            termarrow_fg(
+                color,
+                format_args!(
+                    "{} {}",
+                    value.message(),
+                    format_args!("({}, {})", value.kind(), state).fg_bright_black()
+                ),
+            );
+            for task in value.tasks() {
+                println!("   {}", task.to_string().fg_bright_black());
+            }
         }
     }
 
+    fn confirm(&mut self, question: impl Display) -> Result<bool> {
+        if !self.term.is_interactive() {
+            return Ok(true);
+        }
+        print!("\n{} {} ", question, "[y/N]".fg_bright_black());
+        stdout().flush()?;
+        let mut answer = String::new();
+        stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+    }
+
     fn end(&mut self) {
-        if self.is_interactive() {
+        if self.term.is_interactive() {
             println!();
         }
     }
@@ -129,22 +534,162 @@ impl BundleFmt for DataBundle {
     fn clear(&mut self) {
         self.tasks.clear();
         self.day_with_tasks.clear();
+        self.operations.clear();
+        self.reports.clear();
+        self.imports.clear();
+        self.summaries.clear();
+        self.weeks.clear();
+        self.profiles.clear();
+        self.status = None;
+        self.issues.clear();
+        self.info.clear();
+        self.error.clear();
     }
 }
 
-fn term_task_body(task: &Task<Day>) {
+fn term_task_body(task: &Task<Day>, delta: TimeDelta, time_format: &str) {
     let color = if task.is_active() { Fg::Green } else { Fg::Red };
     termarrow_fg(
         color,
         format_args!(
-            "{} {}",
-            DeltaFmt::new(task.delta()),
+            "{} {}{}",
+            DeltaFmt::new(delta),
             format_args!(
-                "({} - {})",
-                TimeFmt::new(task.start()),
-                TimeFmt::option(task.end())
+                "({} - {}) [{}]",
+                TimeFormatFmt::new(Some(task.start()), time_format),
+                TimeFormatFmt::new(task.end(), time_format),
+                task.id()
             )
-            .fg_bright_black()
+            .fg_bright_black(),
+            TagsFmt::new(task.tags())
         ),
     );
 }
+
+struct TagsFmt<'a> {
+    tags: &'a [String],
+}
+
+impl<'a> TagsFmt<'a> {
+    fn new(tags: &'a [String]) -> Self {
+        Self { tags }
+    }
+}
+
+impl Display for TagsFmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for tag in self.tags {
+            write!(f, " {}", format_args!("#{}", tag).fg_cyan())?;
+        }
+        Ok(())
+    }
+}
+
+struct WeekFmt<'a> {
+    week: &'a WeekSummary,
+}
+
+impl<'a> WeekFmt<'a> {
+    fn new(week: &'a WeekSummary) -> Self {
+        Self { week }
+    }
+}
+
+impl Display for WeekFmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Week of {}", self.week.start().format("%Y.%m.%d"))
+    }
+}
+
+struct WeekRangeFmt<'a> {
+    week: &'a WeekSummary,
+}
+
+impl<'a> WeekRangeFmt<'a> {
+    fn new(week: &'a WeekSummary) -> Self {
+        Self { week }
+    }
+}
+
+impl Display for WeekRangeFmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (Some(first), Some(last)) = (self.week.first_date(), self.week.last_date()) else {
+            return Ok(());
+        };
+        write!(f, "({} - {})", first.format("%d.%m"), last.format("%d.%m"))
+    }
+}
+
+impl TermStyle for WeekRangeFmt<'_> {}
+
+/// A rounded duration in parentheses, followed by the tracked duration if
+/// they differ.
+struct RoundedFmt {
+    delta: TimeDelta,
+    raw_delta: TimeDelta,
+}
+
+impl RoundedFmt {
+    fn new(delta: TimeDelta, raw_delta: TimeDelta) -> Self {
+        Self { delta, raw_delta }
+    }
+}
+
+impl Display for RoundedFmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({}{})",
+            DeltaFmt::new(self.delta),
+            RawFmt::new(self.delta, self.raw_delta)
+        )
+    }
+}
+
+impl TermStyle for RoundedFmt {}
+
+struct RawFmt {
+    delta: TimeDelta,
+    raw_delta: TimeDelta,
+}
+
+impl RawFmt {
+    fn new(delta: TimeDelta, raw_delta: TimeDelta) -> Self {
+        Self { delta, raw_delta }
+    }
+}
+
+impl Display for RawFmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.delta.num_minutes() == self.raw_delta.num_minutes() {
+            return Ok(());
+        }
+        write!(f, ", tracked {}", DeltaFmt::new(self.raw_delta))
+    }
+}
+
+fn term_task_notes(task: &Task<Day>) {
+    for line in task.notes().into_iter().flat_map(str::lines) {
+        println!("   {}", line.fg_bright_black());
+    }
+}
+
+struct TimeFormatFmt<'a> {
+    value: Option<NaiveDateTime>,
+    format: &'a str,
+}
+
+impl<'a> TimeFormatFmt<'a> {
+    fn new(value: Option<NaiveDateTime>, format: &'a str) -> Self {
+        Self { value, format }
+    }
+}
+
+impl Display for TimeFormatFmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "{}", value.format(self.format)),
+            None => write!(f, "..."),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::{env, fmt::Display, fs, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{config::Config, Error, Result};
+
+/// The profile which uses the config file and database of a single setup.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The environment variable, which selects the profile to use.
+pub const PROFILE_VARIABLE: &str = "TTRACE_PROFILE";
+
+/// A named set of settings with its own database, e.g. to keep the tasks of
+/// different employers apart. The settings of a profile are stored in
+/// `profiles/<name>.toml` next to the config file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Profile {
+    name: String,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Result<Self> {
+        let valid = name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_'));
+        if name.is_empty() || !valid {
+            return Err(Error::InvalidInput(format!(
+                "invalid profile name '{}', only letters, digits, '-' and '_' are allowed",
+                name
+            )));
+        }
+        Ok(Self {
+            name: name.to_owned(),
+        })
+    }
+
+    pub fn default_profile() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_owned(),
+        }
+    }
+
+    /// The given profile, the one of `TTRACE_PROFILE` or the profile which was
+    /// switched to last.
+    pub fn current(name: Option<&str>) -> Result<Self> {
+        let name = match name {
+            Some(name) => name.to_owned(),
+            None => match env::var(PROFILE_VARIABLE) {
+                Ok(name) if !name.trim().is_empty() => name.trim().to_owned(),
+                _ => Self::switched()?,
+            },
+        };
+        let profile = Self::new(&name)?;
+        if !profile.exists() {
+            return Err(Error::NotFound(format!("profile {}", name)));
+        }
+        Ok(profile)
+    }
+
+    /// The default profile and all created profiles ordered by name.
+    pub fn list() -> Result<Vec<Self>> {
+        let mut profiles = vec![Self::default_profile()];
+        let directory = profiles_directory();
+        if !directory.exists() {
+            return Ok(profiles);
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "toml")
+            {
+                if let Some(name) = path.file_stem() {
+                    names.push(name.to_string_lossy().into_owned());
+                }
+            }
+        }
+        names.sort();
+        profiles.extend(names.iter().filter_map(|name| Self::new(name).ok()));
+        Ok(profiles)
+    }
+
+    /// Creates the settings of a new profile, they are copied from the given
+    /// config, but the database is stored in its own directory.
+    pub fn create(name: &str, config: &Config) -> Result<Self> {
+        let profile = Self::new(name)?;
+        if profile.exists() {
+            return Err(Error::InvalidInput(format!(
+                "the profile {} already exists",
+                name
+            )));
+        }
+        let default = Self::default_profile().config()?;
+        let path = default.path().join("profiles").join(name);
+        config
+            .clone()
+            .with_path(path)
+            .write(&profile.config_path())?;
+        Ok(profile)
+    }
+
+    /// Uses the profile for all following commands.
+    pub fn switch(&self) -> Result<()> {
+        if !self.exists() {
+            return Err(Error::NotFound(format!("profile {}", self.name)));
+        }
+        let path = switched_path();
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, format!("{}\n", self.name))?;
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PROFILE
+    }
+
+    pub fn exists(&self) -> bool {
+        self.is_default() || self.config_path().exists()
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        match self.is_default() {
+            true => Config::file_path(),
+            false => profiles_directory().join(format!("{}.toml", self.name)),
+        }
+    }
+
+    pub fn config(&self) -> Result<Config> {
+        match self.is_default() {
+            true => Config::load(),
+            false => Config::load_from(&self.config_path()),
+        }
+    }
+
+    /// The name of the profile, which was switched to last.
+    fn switched() -> Result<String> {
+        let path = switched_path();
+        if !path.exists() {
+            return Ok(DEFAULT_PROFILE.to_owned());
+        }
+        let name = fs::read_to_string(path)?;
+        match name.trim() {
+            "" => Ok(DEFAULT_PROFILE.to_owned()),
+            name => Ok(name.to_owned()),
+        }
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "profile \"{}\"", self.name)
+    }
+}
+
+fn profiles_directory() -> PathBuf {
+    Config::directory().join("profiles")
+}
+
+fn switched_path() -> PathBuf {
+    Config::directory().join("profile")
+}
@@ -0,0 +1,276 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use chrono::{NaiveDate, TimeDelta, Weekday};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    rounding::{Rounding, RoundingScope},
+    task::DayWithTasks,
+    time::serialize_minutes,
+    Error, Result,
+};
+
+/// The time spent over a range of days, aggregated by a grouping.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    from: NaiveDate,
+    to: NaiveDate,
+    by: ReportGrouping,
+    #[serde(rename = "total_minutes", serialize_with = "serialize_minutes")]
+    total: TimeDelta,
+    #[serde(rename = "raw_total_minutes", serialize_with = "serialize_minutes")]
+    raw_total: TimeDelta,
+    rounding: Rounding,
+    groups: Vec<ReportGroup>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportGroup {
+    key: String,
+    #[serde(rename = "minutes", serialize_with = "serialize_minutes")]
+    delta: TimeDelta,
+    #[serde(rename = "raw_minutes", serialize_with = "serialize_minutes")]
+    raw_delta: TimeDelta,
+    percentage: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGrouping {
+    Tag,
+    Description,
+    Day,
+    Week,
+}
+
+const UNTAGGED: &str = "(untagged)";
+
+impl Report {
+    /// Tasks with multiple tags count towards every one of their tags, so the
+    /// percentages of a tag report may add up to more than 100%. The total is
+    /// the sum of the rounded days, like they are listed by `ttrace day`.
+    /// Rounding per group rounds the groups of the report, rounding per day
+    /// only changes the groups of days and weeks. Weeks are named by their
+    /// first day.
+    pub fn new(
+        from: NaiveDate,
+        to: NaiveDate,
+        by: ReportGrouping,
+        days: &[DayWithTasks],
+        rounding: Rounding,
+        week_start: Weekday,
+    ) -> Self {
+        let days: Vec<_> = days
+            .iter()
+            .map(|day| day.clone().with_rounding(rounding))
+            .collect();
+        // The durations of the entries are rounded per task or day already.
+        let entries: Vec<(String, (TimeDelta, TimeDelta))> = match by {
+            ReportGrouping::Day | ReportGrouping::Week => days
+                .iter()
+                .filter(|day| !day.is_empty())
+                .map(|day| {
+                    let date = match by {
+                        ReportGrouping::Day => day.day().date(),
+                        _ => day.day().date().week(week_start).first_day(),
+                    };
+                    let key = date.format("%Y-%m-%d").to_string();
+                    (key, (day.raw_delta(), day.delta()))
+                })
+                .collect(),
+            ReportGrouping::Tag | ReportGrouping::Description => days
+                .iter()
+                .flat_map(|day| {
+                    day.tasks()
+                        .flat_map(move |task| {
+                            let delta = task.delta_on(day.day());
+                            let rounded = rounding.round_per(RoundingScope::Task, delta);
+                            let keys = match by {
+                                ReportGrouping::Tag if task.tags().is_empty() => {
+                                    vec![UNTAGGED.to_owned()]
+                                }
+                                ReportGrouping::Tag => task.tags().to_vec(),
+                                _ => vec![task.description().to_owned()],
+                            };
+                            keys.into_iter().map(move |key| (key, (delta, rounded)))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        };
+        let entries = match by {
+            ReportGrouping::Description => merge_case(entries),
+            _ => entries,
+        };
+        let raw_total = days.iter().map(|day| day.raw_delta()).sum();
+        let total = days.iter().map(|day| day.delta()).sum();
+        let mut groups: Vec<_> = entries
+            .into_iter()
+            .into_grouping_map()
+            .fold(
+                (TimeDelta::zero(), TimeDelta::zero()),
+                |(raw_sum, sum), _, (raw_delta, delta)| (raw_sum + raw_delta, sum + delta),
+            )
+            .into_iter()
+            .map(|(key, (raw_delta, delta))| {
+                let delta = match by {
+                    ReportGrouping::Tag | ReportGrouping::Description => {
+                        rounding.round_per(RoundingScope::Group, delta)
+                    }
+                    ReportGrouping::Day | ReportGrouping::Week => delta,
+                };
+                ReportGroup::new(key, delta, raw_delta, raw_total)
+            })
+            .collect();
+        match by {
+            ReportGrouping::Day | ReportGrouping::Week => {
+                groups.sort_by(|a, b| a.key.cmp(&b.key));
+            }
+            ReportGrouping::Tag | ReportGrouping::Description => {
+                groups.sort_by(|a, b| b.delta.cmp(&a.delta).then_with(|| a.key.cmp(&b.key)));
+            }
+        }
+        Self {
+            from,
+            to,
+            by,
+            total,
+            raw_total,
+            rounding,
+            groups,
+        }
+    }
+
+    pub fn from(&self) -> NaiveDate {
+        self.from
+    }
+
+    pub fn to(&self) -> NaiveDate {
+        self.to
+    }
+
+    pub fn by(&self) -> ReportGrouping {
+        self.by
+    }
+
+    pub fn total(&self) -> TimeDelta {
+        self.total
+    }
+
+    pub fn raw_total(&self) -> TimeDelta {
+        self.raw_total
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = &ReportGroup> {
+        self.groups.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+impl ReportGroup {
+    /// The percentage is the share of the tracked time, it is not rounded.
+    fn new(key: String, delta: TimeDelta, raw_delta: TimeDelta, raw_total: TimeDelta) -> Self {
+        let percentage = match raw_total.num_seconds() {
+            0 => 0.0,
+            total => raw_delta.num_seconds() as f64 * 100.0 / total as f64,
+        };
+        Self {
+            key,
+            delta,
+            raw_delta,
+            percentage,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    pub fn delta(&self) -> TimeDelta {
+        self.delta
+    }
+
+    pub fn raw_delta(&self) -> TimeDelta {
+        self.raw_delta
+    }
+
+    pub fn percentage(&self) -> f64 {
+        self.percentage
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "report from={} to={} by={} total={} raw_total={}",
+            self.from.format("%Y-%m-%d"),
+            self.to.format("%Y-%m-%d"),
+            self.by,
+            self.total.num_minutes(),
+            self.raw_total.num_minutes()
+        )
+    }
+}
+
+impl Display for ReportGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "group \"{}\" minutes={} raw_minutes={} percentage={:.1}",
+            self.key,
+            self.delta.num_minutes(),
+            self.raw_delta.num_minutes(),
+            self.percentage
+        )
+    }
+}
+
+impl Display for ReportGrouping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Tag => "tag",
+            Self::Description => "description",
+            Self::Day => "day",
+            Self::Week => "week",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for ReportGrouping {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tag" => Ok(Self::Tag),
+            "description" => Ok(Self::Description),
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown report grouping: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Uses the first spelling for keys, which only differ in their case.
+fn merge_case<T>(entries: Vec<(String, T)>) -> Vec<(String, T)> {
+    let mut spellings: HashMap<String, String> = HashMap::new();
+    entries
+        .into_iter()
+        .map(|(key, delta)| {
+            let key = spellings.entry(key.to_lowercase()).or_insert(key).clone();
+            (key, delta)
+        })
+        .collect()
+}
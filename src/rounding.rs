@@ -0,0 +1,133 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Rounds durations to a multiple of an increment in minutes, an increment of
+/// zero keeps the durations as they are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rounding {
+    pub increment: u32,
+    pub mode: RoundingMode,
+    pub per: RoundingScope,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+    Up,
+    Down,
+    #[default]
+    Nearest,
+}
+
+/// What is rounded, the totals add up the rounded durations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingScope {
+    /// Every task on its own.
+    Task,
+    /// The tasks of a description on a day and the groups of reports.
+    #[default]
+    Group,
+    /// The total of every day.
+    Day,
+}
+
+impl Rounding {
+    pub fn new(increment: u32, mode: RoundingMode) -> Self {
+        Self {
+            increment,
+            mode,
+            per: RoundingScope::default(),
+        }
+    }
+
+    pub fn with_scope(self, per: RoundingScope) -> Self {
+        Self { per, ..self }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.increment == 0
+    }
+
+    pub fn round(&self, delta: TimeDelta) -> TimeDelta {
+        if self.is_none() {
+            return delta;
+        }
+        let increment = i64::from(self.increment) * 60;
+        let seconds = delta.num_seconds();
+        let rest = seconds.rem_euclid(increment);
+        let down = seconds - rest;
+        let rounded = match self.mode {
+            RoundingMode::Down => down,
+            RoundingMode::Up if rest == 0 => down,
+            RoundingMode::Up => down + increment,
+            RoundingMode::Nearest if rest * 2 < increment => down,
+            RoundingMode::Nearest => down + increment,
+        };
+        TimeDelta::seconds(rounded)
+    }
+
+    /// Rounds the duration only, if it is of the scope of the rounding.
+    pub fn round_per(&self, scope: RoundingScope, delta: TimeDelta) -> TimeDelta {
+        match self.per == scope {
+            true => self.round(delta),
+            false => delta,
+        }
+    }
+}
+
+impl Display for RoundingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Nearest => "nearest",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for RoundingMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            "nearest" => Ok(Self::Nearest),
+            _ => Err(Error::InvalidInput(format!("unknown rounding mode: {}", s))),
+        }
+    }
+}
+
+impl Display for RoundingScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Task => "task",
+            Self::Group => "group",
+            Self::Day => "day",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for RoundingScope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "task" => Ok(Self::Task),
+            "group" => Ok(Self::Group),
+            "day" => Ok(Self::Day),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown rounding scope: {}",
+                s
+            ))),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! A single line about the running task for shell prompts and status bars.
+
+use std::fmt::Write;
+
+use chrono::{NaiveTime, TimeDelta};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+    day::Day,
+    task::{DayWithTasks, Task},
+    time::serialize_minutes,
+    Error, Result,
+};
+
+/// The template of `ttrace status`, if none is given.
+pub const DEFAULT_TEMPLATE: &str = "{description} {elapsed}";
+
+/// The placeholders, which can be used in a template.
+const PLACEHOLDERS: [&str; 6] = ["id", "description", "tags", "start", "elapsed", "day_total"];
+
+/// The running task and the time spent today.
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    task: Option<Task<Day>>,
+    #[serde(rename = "day_total_minutes", serialize_with = "serialize_minutes")]
+    day_total: TimeDelta,
+}
+
+impl Status {
+    /// `today` is missing, if no task was started today. A running task then
+    /// started on a previous day and counts from midnight.
+    pub fn new(task: Option<Task<Day>>, today: Option<&DayWithTasks>) -> Self {
+        let day_total = match (today, &task) {
+            (Some(today), _) => today.delta(),
+            (None, Some(task)) => {
+                let midnight = task.day().clock().today().and_time(NaiveTime::MIN);
+                task.end_or_now() - task.start().max(midnight)
+            }
+            (None, None) => TimeDelta::zero(),
+        };
+        Self { task, day_total }
+    }
+
+    pub fn task(&self) -> Option<&Task<Day>> {
+        self.task.as_ref()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn day_total(&self) -> TimeDelta {
+        self.day_total
+    }
+
+    /// Replaces the placeholders like `{description}` in the template, braces
+    /// are escaped by doubling them. Without a running task, the task fields
+    /// are empty.
+    pub fn render(&self, template: &str, time_format: &str) -> Result<String> {
+        let mut result = String::with_capacity(template.len());
+        let mut chars = template.chars();
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    result.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    result.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let (name, _) = rest.split_once('}').ok_or_else(|| {
+                        Error::InvalidInput(format!("unclosed placeholder in {:?}", template))
+                    })?;
+                    self.placeholder(&mut result, name, time_format)?;
+                    chars = rest[name.len() + 1..].chars();
+                }
+                '}' => {
+                    return Err(Error::InvalidInput(format!(
+                        "unmatched '}}' in {:?}, write '}}}}' for a brace",
+                        template
+                    )))
+                }
+                char => result.push(char),
+            }
+        }
+        Ok(result)
+    }
+
+    /// The custom module format of waybar, the class is `active` or `idle`.
+    pub fn waybar(&self, text: String, time_format: &str) -> Value {
+        let class = match self.is_active() {
+            true => "active",
+            false => "idle",
+        };
+        json!({
+            "text": text,
+            "tooltip": self.tooltip(time_format),
+            "class": class,
+            "alt": class,
+        })
+    }
+
+    /// The json format of i3blocks, the short text only shows the duration.
+    pub fn i3blocks(&self, text: String) -> Value {
+        let short_text = self
+            .task
+            .as_ref()
+            .map(|task| format_delta(task.delta()))
+            .unwrap_or_default();
+        json!({
+            "full_text": text,
+            "short_text": short_text,
+        })
+    }
+
+    fn tooltip(&self, time_format: &str) -> String {
+        let total = format!("today {}", format_delta(self.day_total));
+        match &self.task {
+            Some(task) => format!(
+                "{} since {}\n{}",
+                task.description(),
+                task.start().format(time_format),
+                total
+            ),
+            None => total,
+        }
+    }
+
+    fn placeholder(&self, result: &mut String, name: &str, time_format: &str) -> Result<()> {
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(Error::InvalidInput(format!(
+                "unknown placeholder {{{}}}, use one of {}",
+                name,
+                PLACEHOLDERS.map(|name| format!("{{{}}}", name)).join(", ")
+            )));
+        }
+        if name == "day_total" {
+            result.push_str(&format_delta(self.day_total));
+            return Ok(());
+        }
+        let Some(task) = &self.task else {
+            return Ok(());
+        };
+        _ = match name {
+            "id" => write!(result, "{}", task.id()),
+            "description" => write!(result, "{}", task.description()),
+            "tags" => write!(
+                result,
+                "{}",
+                task.tags()
+                    .iter()
+                    .map(|tag| format!("#{}", tag))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            "start" => write!(result, "{}", task.start().format(time_format)),
+            _ => write!(result, "{}", format_delta(task.delta())),
+        };
+        Ok(())
+    }
+}
+
+/// Hours and minutes like `1:05`.
+fn format_delta(delta: TimeDelta) -> String {
+    format!("{}:{:02}", delta.num_hours(), delta.num_minutes() % 60)
+}
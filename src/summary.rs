@@ -0,0 +1,153 @@
+use std::fmt::Display;
+
+use chrono::{NaiveDate, TimeDelta, Weekday};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{task::DayWithTasks, time::serialize_minutes};
+
+/// The totals of a range of days, grouped by the weeks starting on the
+/// configured weekday.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    from: NaiveDate,
+    to: NaiveDate,
+    #[serde(rename = "total_minutes", serialize_with = "serialize_minutes")]
+    total: TimeDelta,
+    weeks: Vec<WeekSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeekSummary {
+    /// The first day of the week, which may lie before the first day.
+    start: NaiveDate,
+    #[serde(rename = "total_minutes", serialize_with = "serialize_minutes")]
+    total: TimeDelta,
+    days: Vec<DaySummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DaySummary {
+    date: NaiveDate,
+    #[serde(rename = "total_minutes", serialize_with = "serialize_minutes")]
+    total: TimeDelta,
+}
+
+impl Summary {
+    pub fn new(from: NaiveDate, to: NaiveDate, days: &[DayWithTasks], week_start: Weekday) -> Self {
+        let weeks: Vec<_> = days
+            .iter()
+            .map(DaySummary::from_day_with_tasks)
+            .group_by(|day| day.date.week(week_start).first_day())
+            .into_iter()
+            .map(|(start, days)| WeekSummary::new(start, days.collect()))
+            .collect();
+        let total = weeks.iter().map(|week| week.total).sum();
+        Self {
+            from,
+            to,
+            total,
+            weeks,
+        }
+    }
+
+    pub fn from(&self) -> NaiveDate {
+        self.from
+    }
+
+    pub fn to(&self) -> NaiveDate {
+        self.to
+    }
+
+    pub fn total(&self) -> TimeDelta {
+        self.total
+    }
+
+    pub fn weeks(&self) -> impl Iterator<Item = &WeekSummary> {
+        self.weeks.iter()
+    }
+}
+
+impl WeekSummary {
+    pub fn new(start: NaiveDate, days: Vec<DaySummary>) -> Self {
+        let total = days.iter().map(|day| day.total).sum();
+        Self { start, total, days }
+    }
+
+    pub fn from_days(days: &[DayWithTasks], week_start: Weekday) -> Option<Self> {
+        let start = days.last()?.day().date().week(week_start).first_day();
+        let days = days.iter().map(DaySummary::from_day_with_tasks).collect();
+        Some(Self::new(start, days))
+    }
+
+    pub fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    pub fn total(&self) -> TimeDelta {
+        self.total
+    }
+
+    pub fn first_date(&self) -> Option<NaiveDate> {
+        self.days.first().map(|day| day.date)
+    }
+
+    pub fn last_date(&self) -> Option<NaiveDate> {
+        self.days.last().map(|day| day.date)
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = &DaySummary> {
+        self.days.iter()
+    }
+}
+
+impl DaySummary {
+    fn from_day_with_tasks(day_with_tasks: &DayWithTasks) -> Self {
+        Self {
+            date: day_with_tasks.day().date(),
+            total: day_with_tasks.delta(),
+        }
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn total(&self) -> TimeDelta {
+        self.total
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "summary from={} to={} minutes={}",
+            self.from.format("%Y-%m-%d"),
+            self.to.format("%Y-%m-%d"),
+            self.total.num_minutes()
+        )
+    }
+}
+
+impl Display for WeekSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "week {} minutes={}",
+            self.start.format("%Y-%m-%d"),
+            self.total.num_minutes()
+        )
+    }
+}
+
+impl Display for DaySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "day {} minutes={}",
+            self.date.format("%Y-%m-%d"),
+            self.total.num_minutes()
+        )
+    }
+}
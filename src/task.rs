@@ -1,12 +1,18 @@
 use std::rc::Rc;
 
-use chrono::{Local, NaiveTime, TimeDelta};
-use eyre::{eyre, Context};
+use chrono::{NaiveDateTime, TimeDelta};
+use itertools::Itertools;
 use rusqlite::{Connection, Params, Row};
 
 pub use dto::{DayWithTasks, Task, TaskGroup};
 
-use crate::day::{Day, DayRef};
+use crate::{
+    clock::Clock,
+    day::{day_from_row, Day, DayRef},
+    journal::JournalRepository,
+    rounding::Rounding,
+    Error, Result,
+};
 
 use self::dto::MutTask;
 
@@ -14,59 +20,308 @@ mod dto;
 
 pub struct TaskRepository {
     connection: Rc<Connection>,
+    journal: Rc<JournalRepository>,
+    clock: Clock,
+    rounding: Rounding,
 }
 
 impl TaskRepository {
-    pub fn day_with_tasks(&self, day: Day) -> eyre::Result<DayWithTasks> {
-        let mut tasks = self
+    pub fn day_with_tasks(&self, day: Day) -> Result<DayWithTasks> {
+        let tasks = self
             .query(
-                "SELECT id, day_id, start, end, description FROM tasks WHERE day_id=?1",
-                (day.id(),),
-            )
-            .with_context(|| format!("cannot query tasks for day: {:?}", day))?;
-        let tasks = tasks
+                "SELECT id, day_id, start, end, description, notes
+                 FROM tasks
+                 WHERE start < ?2 AND (end IS null OR end > ?1) AND deleted_at IS null
+                 ORDER BY start",
+                (day.start(), day.end()),
+            )?
+            .into_iter()
+            .map(|task| match task.day() == day.id() {
+                true => Ok(MutTask::with_day(task, day)),
+                false => self.resolve(task),
+            })
+            .collect::<Result<_>>()?;
+        Ok(DayWithTasks::new(day, tasks).with_rounding(self.rounding))
+    }
+
+    pub fn start(&self, day: Day, description: &str, tags: &[String]) -> Result<Task<Day>> {
+        self.start_at(day, self.clock.now(), description, tags)
+    }
+
+    /// Starts a task at the given time, which must not lie in the future. The
+    /// running task is stopped at that time and finished tasks reaching past it
+    /// are trimmed, like `set_start` adjusts the previous task. A paused task is
+    /// no longer resumed afterwards.
+    pub fn start_at(
+        &self,
+        day: Day,
+        time: NaiveDateTime,
+        description: &str,
+        tags: &[String],
+    ) -> Result<Task<Day>> {
+        let now = self.clock.now();
+        if time > now {
+            return Err(Error::InvalidTime(format!(
+                "cannot start a task in the future: {}",
+                time
+            )));
+        }
+        let current = match self.current() {
+            Ok(current) => Some(current),
+            Err(Error::NoActiveTask) => None,
+            Err(error) => return Err(error),
+        };
+        if let Some(current) = &current {
+            if time <= current.start() {
+                return Err(Error::InvalidTime(format!(
+                    "cannot start before the running {} started",
+                    current
+                )));
+            }
+        }
+        let finished: Vec<_> = self
+            .overlapping(time, now)?
             .into_iter()
-            .map(|task| MutTask::with_day(task, day))
+            .filter(|task| !task.is_active())
             .collect();
-        Ok(DayWithTasks::new(day, tasks))
+        if let Some(task) = finished.iter().find(|task| task.start() >= time) {
+            return Err(Error::Overlap(format!(
+                "the task would cover the {} completely",
+                task
+            )));
+        }
+        if let Some(current) = current {
+            self.update_end(current, time)?;
+        }
+        for task in finished {
+            self.update_end(task, time)?;
+        }
+        self.clear_pauses()?;
+        let task = self.insert(day, time, None, description)?;
+        self.set_tags(task, tags)
     }
 
-    pub fn start(&self, day: Day, description: &str) -> eyre::Result<Task<Day>> {
-        let desciption = description.trim();
-        if self.current(day).is_ok() {
-            self.stop(day)
-                .with_context(|| "could not end the current task before starting a new one.")?;
+    /// Records a finished task, which must not overlap with other tasks.
+    pub fn add(
+        &self,
+        day: Day,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        description: &str,
+        tags: &[String],
+    ) -> Result<Task<Day>> {
+        if end <= start {
+            return Err(Error::InvalidTime(format!(
+                "cannot add a task which ends before it starts: {} <= {}",
+                end, start
+            )));
+        }
+        let overlapping = self.overlapping(start, end)?;
+        if !overlapping.is_empty() {
+            let tasks = overlapping.iter().map(|task| task.to_string()).join(", ");
+            return Err(Error::Overlap(format!(
+                "the task overlaps with other tasks: {}",
+                tasks
+            )));
+        }
+        let task = self.insert(day, start, Some(end), description)?;
+        self.set_tags(task, tags)
+    }
+
+    /// Trims the tasks overlapping the given range, the same way `set_start`
+    /// adjusts the previous task.
+    pub fn make_room(&self, start: NaiveDateTime, end: NaiveDateTime) -> Result<()> {
+        let overlapping = self.overlapping(start, end)?;
+        for task in overlapping.iter() {
+            if task.start() < start && task.end_or_now() > end {
+                return Err(Error::Overlap(format!(
+                    "the range is enclosed by the {}",
+                    task
+                )));
+            }
+            if task.start() >= start && task.end_or_now() <= end {
+                return Err(Error::Overlap(format!(
+                    "the range covers the {} completely",
+                    task
+                )));
+            }
+        }
+        for task in overlapping {
+            if task.start() < start {
+                self.update_end(task, start)?;
+            } else {
+                self.update_start(task, end)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stop(&self) -> Result<Task<Day>> {
+        self.stop_at(self.clock.now())
+    }
+
+    /// Stops the running task at the given time, which has to lie between its
+    /// start and now.
+    pub fn stop_at(&self, time: NaiveDateTime) -> Result<Task<Day>> {
+        let current = self.current()?;
+        if time > self.clock.now() {
+            return Err(Error::InvalidTime(format!(
+                "cannot stop a task in the future: {}",
+                time
+            )));
+        }
+        self.update_end(current, time)
+    }
+
+    /// Stops the running task and marks it as paused, so that it is resumed
+    /// even if other tasks are added until then.
+    pub fn pause(&self) -> Result<Task<Day>> {
+        let task = self.stop()?;
+        self.clear_pauses()?;
+        self.set_paused(task.id(), true)?;
+        Ok(task)
+    }
+
+    /// Starts a new task like the paused one or, if no task is paused, like
+    /// the one which ended last.
+    pub fn resume(&self, day: Day) -> Result<Task<Day>> {
+        match self.current() {
+            Ok(current) => {
+                return Err(Error::InvalidInput(format!(
+                    "the {} is still running",
+                    current
+                )))
+            }
+            Err(Error::NoActiveTask) => {}
+            Err(error) => return Err(error),
+        }
+        let task = match self.paused()? {
+            Some(task) => task,
+            None => self.last()?,
         };
-        let now = Local::now().time();
-        self.connection
-            .execute(
-                "INSERT INTO tasks (day_id, start, end, description)
-                 VALUES (?1, ?2, null, ?3)",
-                (day.id(), now, description),
-            )
-            .wrap_err("could not start a new task")
-            .with_context(|| description.to_owned())?;
-        self.current(day)
-            .wrap_err("could not get newly created task")
+        self.continue_task(day, &task)
+    }
+
+    /// Starts a new task with the description and tags of the given task.
+    pub fn continue_task<DayRefImpl>(&self, day: Day, task: &Task<DayRefImpl>) -> Result<Task<Day>>
+    where
+        DayRefImpl: DayRef,
+    {
+        self.start(day, task.description(), task.tags())
+    }
+
+    /// Ends the task at the given time and continues it from there as a new task
+    /// with the same tags, which lasts until the old end or is still running.
+    /// The new task belongs to the day of the time.
+    pub fn split(
+        &self,
+        task: Task<Day>,
+        day: Day,
+        time: NaiveDateTime,
+        description: Option<&str>,
+    ) -> Result<(Task<Day>, Task<Day>)> {
+        if time <= task.start() || time >= task.end_or_now() {
+            return Err(Error::InvalidTime(format!(
+                "the time {} must lie within the {}",
+                time, task
+            )));
+        }
+        let end = task.end();
+        let tags = task.tags().to_vec();
+        let description = description.unwrap_or(task.description()).to_owned();
+        let first = self.update_end(task, time)?;
+        let second = self.insert(day, time, end, &description)?;
+        let second = self.set_tags(second, &tags)?;
+        Ok((first, second))
     }
 
-    pub fn stop(&self, day: Day) -> eyre::Result<Task<Day>> {
-        let mut current = self
-            .current(day)
-            .wrap_err("a current task is needed to stop it")
-            .with_context(|| format!("{:?}", day))?;
-        if current.end().is_some() {
-            let error = Err(eyre!("task already has an end!"));
-            return error.with_context(|| format!("{:?}", current));
+    /// Combines the tasks into the earliest of them, which then lasts from the
+    /// earliest start to the latest end. The tags are combined and the notes
+    /// appended, the other tasks are deleted. Gaps between the tasks are only
+    /// absorbed with `absorb_gaps`, other tasks must not lie in between.
+    pub fn merge(&self, mut tasks: Vec<Task<Day>>, absorb_gaps: bool) -> Result<Task<Day>> {
+        tasks.sort_by_key(|task| task.start());
+        tasks.dedup_by_key(|task| task.id());
+        if tasks.len() < 2 {
+            return Err(Error::InvalidInput(
+                "at least two different tasks are needed to merge".to_owned(),
+            ));
+        }
+        let start = tasks[0].start();
+        let end = match tasks.iter().any(|task| task.is_active()) {
+            true => None,
+            false => tasks.iter().filter_map(|task| task.end()).max(),
+        };
+        let end_or_now = tasks.iter().map(|task| task.end_or_now()).max().unwrap();
+        if let Some(other) = self
+            .overlapping(start, end_or_now)?
+            .into_iter()
+            .find(|other| tasks.iter().all(|task| task.id() != other.id()))
+        {
+            return Err(Error::Overlap(format!(
+                "the {} lies between the tasks to merge",
+                other
+            )));
+        }
+        if !absorb_gaps {
+            let mut latest_end = tasks[0].end_or_now();
+            for task in tasks.iter().skip(1) {
+                if task.start() > latest_end {
+                    return Err(Error::InvalidInput(format!(
+                        "there is a gap before the {}, absorb it to merge the tasks",
+                        task
+                    )));
+                }
+                latest_end = latest_end.max(task.end_or_now());
+            }
+        }
+        let tags: Vec<String> = tasks.iter().flat_map(|task| task.tags()).cloned().collect();
+        let notes = tasks.iter().filter_map(|task| task.notes()).join("\n");
+        let mut tasks = tasks.into_iter();
+        let mut merged = tasks.next().unwrap();
+        for task in tasks {
+            self.delete(task)?;
+        }
+        match end {
+            Some(end) => MutTask::set_end(&mut merged, end),
+            None => MutTask::clear_end(&mut merged),
+        }
+        MutTask::set_tags(&mut merged, normalize_tags(&tags));
+        MutTask::set_notes(&mut merged, Some(notes).filter(|notes| !notes.is_empty()));
+        self.save(&merged)?;
+        Ok(merged)
+    }
+
+    /// Merges consecutive tasks of the day with the same description, ignoring
+    /// the case. Only tasks which follow each other directly are merged, unless
+    /// gaps are absorbed.
+    pub fn compact(&self, day: Day, absorb_gaps: bool) -> Result<Vec<Task<Day>>> {
+        let mut runs: Vec<Vec<Task<Day>>> = Vec::new();
+        for task in self
+            .day_with_tasks(day)?
+            .tasks()
+            .filter(|task| task.day().id() == day.id())
+        {
+            match runs.last_mut() {
+                Some(run)
+                    if run.last().is_some_and(|last| {
+                        last.description().to_lowercase() == task.description().to_lowercase()
+                            && (absorb_gaps || task.start() <= last.end_or_now())
+                    }) =>
+                {
+                    run.push(task.clone())
+                }
+                _ => runs.push(vec![task.clone()]),
+            }
         }
-        let now = Local::now().time();
-        MutTask::set_end(&mut current, now);
-        self.save(&current);
-        Ok(current)
+        runs.into_iter()
+            .filter(|run| run.len() > 1)
+            .map(|run| self.merge(run, absorb_gaps))
+            .collect()
     }
 
-    pub fn rename_current(&self, day: Day, description: &str) -> eyre::Result<Task<Day>> {
-        let task = self.current(day)?;
+    pub fn rename_current(&self, description: &str) -> Result<Task<Day>> {
+        let task = self.current()?;
         self.rename_task(task, description)
     }
 
@@ -74,7 +329,7 @@ impl TaskRepository {
         &self,
         mut task: Task<DayRefImpl>,
         description: &str,
-    ) -> eyre::Result<Task<DayRefImpl>>
+    ) -> Result<Task<DayRefImpl>>
     where
         DayRefImpl: DayRef,
     {
@@ -83,135 +338,460 @@ impl TaskRepository {
         Ok(task)
     }
 
-    pub fn shift_start(&self, task: Task<Day>, delta: TimeDelta) -> eyre::Result<Task<Day>> {
+    /// Appends a line to the notes of the task.
+    pub fn add_note<DayRefImpl>(
+        &self,
+        mut task: Task<DayRefImpl>,
+        note: &str,
+    ) -> Result<Task<DayRefImpl>>
+    where
+        DayRefImpl: DayRef,
+    {
+        let note = note.trim();
+        if note.is_empty() {
+            return Err(Error::InvalidInput("the note is empty".to_owned()));
+        }
+        let notes = match task.notes() {
+            Some(notes) => format!("{}\n{}", notes, note),
+            None => note.to_owned(),
+        };
+        MutTask::set_notes(&mut task, Some(notes));
+        self.save(&task)?;
+        Ok(task)
+    }
+
+    pub fn set_tags<DayRefImpl>(
+        &self,
+        mut task: Task<DayRefImpl>,
+        tags: &[String],
+    ) -> Result<Task<DayRefImpl>>
+    where
+        DayRefImpl: DayRef,
+    {
+        MutTask::set_tags(&mut task, normalize_tags(tags));
+        self.save(&task)?;
+        Ok(task)
+    }
+
+    pub fn shift_start(&self, task: Task<Day>, delta: TimeDelta) -> Result<Task<Day>> {
         let time = task.start() + delta;
         self.set_start(task, time)
     }
 
-    pub fn set_start(&self, mut task: Task<Day>, time: NaiveTime) -> eyre::Result<Task<Day>> {
+    pub fn set_start(&self, task: Task<Day>, time: NaiveDateTime) -> Result<Task<Day>> {
         if time == task.start() {
             return Ok(task);
         }
-        if time > task.start() {
-            if time >= task.end_or_day_time() {
-                return Err(eyre!("cannot set start past the end time"));
-            }
+        if time > task.start() && time >= task.end_or_now() {
+            return Err(Error::InvalidTime(
+                "cannot set start past the end time".to_owned(),
+            ));
         }
         if let Some(prev) = self.prev(&task)? {
-            let prev_end = prev.end_or_day_time();
+            let prev_end = prev.end_or_now();
             if prev_end == task.start() || prev_end > time {
-                _ = self.set_end(prev, time)?;
+                _ = self.update_end(prev, time)?;
             }
         }
-        MutTask::set_start(&mut task, time);
-        self.save(&task)?;
-        Ok(task)
+        self.update_start(task, time)
     }
 
-    pub fn set_end(&self, mut task: Task<Day>, time: NaiveTime) -> eyre::Result<Task<Day>> {
+    pub fn shift_end(&self, task: Task<Day>, delta: TimeDelta) -> Result<Task<Day>> {
+        let time = task.end_or_now() + delta;
+        self.set_end(task, time)
+    }
+
+    pub fn set_end(&self, task: Task<Day>, time: NaiveDateTime) -> Result<Task<Day>> {
+        if task.end() == Some(time) {
+            return Ok(task);
+        }
         if time <= task.start() {
-            return Err(eyre!(
+            return Err(Error::InvalidTime(format!(
                 "cannot set end time before start time: {} <= {}",
                 time,
                 task.start()
-            ));
+            )));
+        }
+        if let Some(next) = self.next(&task)? {
+            let next_start = next.start();
+            if next_start == task.end_or_now() || next_start < time {
+                _ = self.update_start(next, time)?;
+            }
+        }
+        self.update_end(task, time)
+    }
+
+    /// Moves the task to the new start and end, which are checked together. A
+    /// task moved past its old end gets its new end first, so that the start
+    /// never lies past the end in between.
+    pub fn set_range(
+        &self,
+        task: Task<Day>,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Task<Day>> {
+        if end <= start {
+            return Err(Error::InvalidTime(format!(
+                "cannot set end time before start time: {} <= {}",
+                end, start
+            )));
+        }
+        match start >= task.end_or_now() {
+            true => {
+                let task = self.set_end(task, end)?;
+                self.set_start(task, start)
+            }
+            false => {
+                let task = self.set_start(task, start)?;
+                self.set_end(task, end)
+            }
         }
-        MutTask::set_end(&mut task, time);
-        self.save(&task)?;
-        Ok(task)
     }
 
-    pub fn current(&self, day: Day) -> eyre::Result<Task<Day>> {
-        let task = self.get(
-            "SELECT id, day_id, start, end, description
+    /// The running task, which may have been started on a previous day.
+    pub fn current(&self) -> Result<Task<Day>> {
+        let task = self
+            .get(
+                "SELECT id, day_id, start, end, description, notes
              FROM tasks
-             WHERE day_id=?1 AND end IS null",
-            (day.id(),),
-        )?;
-        Ok(MutTask::with_day(task, day))
+             WHERE end IS null AND deleted_at IS null
+             ORDER BY start DESC
+             LIMIT 1",
+                (),
+            )
+            .map_err(|err| match err {
+                Error::NotFound(_) => Error::NoActiveTask,
+                err => err,
+            })?;
+        self.resolve(task)
     }
 
-    pub fn prev(&self, task: &Task<Day>) -> eyre::Result<Option<Task<Day>>> {
-        let prev = self
-            .get_opt(
-                "SELECT id, day_id, start, end, description
+    /// The finished task, which ended last.
+    pub fn last(&self) -> Result<Task<Day>> {
+        let task = self
+            .get(
+                "SELECT id, day_id, start, end, description, notes
                  FROM tasks
-                 WHERE day_id=?1 AND end <= ?2
+                 WHERE end IS NOT null AND deleted_at IS null
                  ORDER BY end DESC
                  LIMIT 1",
-                (task.day_id(), task.start()),
-            )?
-            .map(|prev| MutTask::with_day(prev, task.day()));
-        Ok(prev)
+                (),
+            )
+            .map_err(|err| match err {
+                Error::NotFound(_) => Error::NotFound("task to continue".to_owned()),
+                err => err,
+            })?;
+        self.resolve(task)
+    }
+
+    /// The task which was paused last and not resumed since.
+    pub fn paused(&self) -> Result<Option<Task<Day>>> {
+        self.get_opt(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE paused = 1 AND deleted_at IS null
+             ORDER BY end DESC
+             LIMIT 1",
+            (),
+        )?
+        .map(|task| self.resolve(task))
+        .transpose()
+    }
+
+    pub fn prev(&self, task: &Task<Day>) -> Result<Option<Task<Day>>> {
+        self.get_opt(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE end <= ?1 AND id != ?2 AND deleted_at IS null
+             ORDER BY end DESC
+             LIMIT 1",
+            (task.start(), task.id()),
+        )?
+        .map(|prev| self.resolve(prev))
+        .transpose()
+    }
+
+    pub fn next(&self, task: &Task<Day>) -> Result<Option<Task<Day>>> {
+        self.get_opt(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE start >= ?1 AND id != ?2 AND deleted_at IS null
+             ORDER BY start ASC
+             LIMIT 1",
+            (task.start(), task.id()),
+        )?
+        .map(|next| self.resolve(next))
+        .transpose()
     }
 
-    pub fn task(&self, id: u64) -> eyre::Result<Task<u64>> {
+    /// All tasks which are not deleted ordered by their start, the days are not
+    /// resolved, so that tasks of missing days can be read too.
+    pub fn all_tasks(&self) -> Result<Vec<Task<u64>>> {
+        self.query(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE deleted_at IS null
+             ORDER BY start, id",
+            (),
+        )
+    }
+
+    /// Moves the task to another day without changing its times.
+    pub fn assign_day(&self, task: Task<u64>, day: Day) -> Result<Task<Day>> {
+        let task = MutTask::with_day(task, day);
+        self.save(&task)?;
+        Ok(task)
+    }
+
+    pub fn overlapping(&self, start: NaiveDateTime, end: NaiveDateTime) -> Result<Vec<Task<Day>>> {
+        self.query(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE start < ?2 AND (end IS null OR end > ?1) AND deleted_at IS null
+             ORDER BY start",
+            (start, end),
+        )?
+        .into_iter()
+        .map(|task| self.resolve(task))
+        .collect()
+    }
+
+    pub fn task(&self, id: u64) -> Result<Task<u64>> {
         self.get(
-            "SELECT id, day_id, start, end, description
+            "SELECT id, day_id, start, end, description, notes
              FROM tasks
-             WHERE id=?1",
+             WHERE id=?1 AND deleted_at IS null",
             (id,),
         )
+        .map_err(|err| match err {
+            Error::NotFound(_) => Error::NotFound(format!("task with id {}", id)),
+            err => err,
+        })
     }
-}
 
-impl TaskRepository {
-    pub fn new(connection: Rc<Connection>) -> eyre::Result<Self> {
-        let _ = connection.execute(
-            "CREATE TABLE IF NOT EXISTS tasks (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                day_id INTEGER NOT NULL,
-                start DATE NOT NULL,
-                end DATE,
-                description TEXT NOT NULL
-            )",
-            (),
+    pub fn deleted_task(&self, id: u64) -> Result<Task<u64>> {
+        self.get(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE id=?1 AND deleted_at IS NOT null",
+            (id,),
+        )
+        .map_err(|err| match err {
+            Error::NotFound(_) => Error::NotFound(format!("deleted task with id {}", id)),
+            err => err,
+        })
+    }
+
+    pub fn deleted_tasks(&self, count: usize) -> Result<Vec<Task<Day>>> {
+        self.query(
+            "SELECT id, day_id, start, end, description, notes
+             FROM tasks
+             WHERE deleted_at IS NOT null
+             ORDER BY deleted_at DESC
+             LIMIT ?1",
+            (count,),
+        )?
+        .into_iter()
+        .map(|task| self.resolve(task))
+        .collect()
+    }
+
+    /// Marks the task as deleted, it is kept in the database to be restored.
+    pub fn delete(&self, task: Task<Day>) -> Result<Task<Day>> {
+        let now = self.clock.now();
+        let before = self.journal.snapshot(task.id())?;
+        self.connection.execute(
+            "UPDATE tasks SET deleted_at=?1 WHERE id=?2",
+            (now, task.id()),
         )?;
-        Ok(Self { connection })
+        self.journal.record(task.id(), before)?;
+        Ok(task)
     }
 
-    fn get_opt(&self, query: &str, params: impl Params) -> eyre::Result<Option<Task<u64>>> {
-        let tasks = self.query(query, params)?;
-        if tasks.len() == 0 {
-            return Ok(None);
+    pub fn restore(&self, task: Task<Day>) -> Result<Task<Day>> {
+        // A running task lasts until now and only one task may be running.
+        if task.is_active() {
+            match self.current() {
+                Ok(current) => {
+                    return Err(Error::Overlap(format!(
+                        "cannot restore the running {}, the {} is running",
+                        task, current
+                    )))
+                }
+                Err(Error::NoActiveTask) => {}
+                Err(error) => return Err(error),
+            }
         }
-        if tasks.len() == 1 {
-            let task = &tasks[0];
-            return Ok(Some(task.clone()));
+        let end = task.end().unwrap_or_else(|| self.clock.now());
+        let overlapping = self.overlapping(task.start(), end)?;
+        if !overlapping.is_empty() {
+            let tasks = overlapping.iter().map(|task| task.to_string()).join(", ");
+            return Err(Error::Overlap(format!(
+                "cannot restore the {}, it overlaps with other tasks: {}",
+                task, tasks
+            )));
         }
-        Err(eyre!("to many elements"))
+        let before = self.journal.snapshot(task.id())?;
+        self.connection
+            .execute("UPDATE tasks SET deleted_at=null WHERE id=?1", (task.id(),))?;
+        self.journal.record(task.id(), before)?;
+        Ok(task)
+    }
+
+    pub fn resolve(&self, task: Task<u64>) -> Result<Task<Day>> {
+        let day = self.connection.query_row(
+            "SELECT id, date FROM days WHERE id=?1",
+            (task.day(),),
+            |row| day_from_row(row, self.clock),
+        )?;
+        Ok(MutTask::with_day(task, day))
     }
 
-    fn get(&self, query: &str, params: impl Params) -> eyre::Result<Task<u64>> {
+    fn insert(
+        &self,
+        day: Day,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+        description: &str,
+    ) -> Result<Task<Day>> {
+        let description = description.trim();
+        self.connection.execute(
+            "INSERT INTO tasks (day_id, start, end, description)
+             VALUES (?1, ?2, ?3, ?4)",
+            (day.id(), start, end, description),
+        )?;
+        let id = self.connection.last_insert_rowid() as u64;
+        self.journal.record(id, None)?;
+        let task = self.task(id)?;
+        Ok(MutTask::with_day(task, day))
+    }
+
+    fn set_paused(&self, task_id: u64, paused: bool) -> Result<()> {
+        let before = self.journal.snapshot(task_id)?;
         self.connection
+            .execute("UPDATE tasks SET paused=?1 WHERE id=?2", (paused, task_id))?;
+        self.journal.record(task_id, before)
+    }
+
+    fn clear_pauses(&self) -> Result<()> {
+        let ids = self
+            .connection
+            .prepare("SELECT id FROM tasks WHERE paused = 1")?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<u64>>>()?;
+        for id in ids {
+            self.set_paused(id, false)?;
+        }
+        Ok(())
+    }
+
+    fn update_start(&self, mut task: Task<Day>, time: NaiveDateTime) -> Result<Task<Day>> {
+        if let Some(end) = task.end() {
+            if time >= end {
+                return Err(Error::InvalidTime(format!(
+                    "cannot set start time after end time: {} >= {}",
+                    time, end
+                )));
+            }
+        }
+        MutTask::set_start(&mut task, time);
+        self.save(&task)?;
+        Ok(task)
+    }
+
+    pub(crate) fn update_end(&self, mut task: Task<Day>, time: NaiveDateTime) -> Result<Task<Day>> {
+        if time <= task.start() {
+            return Err(Error::InvalidTime(format!(
+                "cannot set end time before start time: {} <= {}",
+                time,
+                task.start()
+            )));
+        }
+        MutTask::set_end(&mut task, time);
+        self.save(&task)?;
+        Ok(task)
+    }
+}
+
+impl TaskRepository {
+    pub fn new(connection: Rc<Connection>, journal: Rc<JournalRepository>, clock: Clock) -> Self {
+        Self {
+            connection,
+            journal,
+            clock,
+            rounding: Rounding::default(),
+        }
+    }
+
+    /// Rounds the totals of the days read by `day_with_tasks`.
+    pub fn with_rounding(self, rounding: Rounding) -> Self {
+        Self { rounding, ..self }
+    }
+
+    fn get_opt(&self, query: &str, params: impl Params) -> Result<Option<Task<u64>>> {
+        let tasks = self.query(query, params)?;
+        if tasks.len() > 1 {
+            return Err(Error::InvalidInput(format!(
+                "expected at most one task, but found {}",
+                tasks.len()
+            )));
+        }
+        Ok(tasks.into_iter().next())
+    }
+
+    fn get(&self, query: &str, params: impl Params) -> Result<Task<u64>> {
+        let task = self
+            .connection
             .query_row(query, params, task_from_row)
-            .wrap_err("could not execute sql statement")
-            .with_context(|| query.to_owned())
+            .map_err(|err| match err {
+                rusqlite::Error::QueryReturnedNoRows => Error::NotFound("task".to_owned()),
+                err => Error::Database(err),
+            })?;
+        self.with_tags(task)
     }
 
-    fn query(&self, query: &str, params: impl Params) -> eyre::Result<Vec<Task<u64>>> {
+    fn query(&self, query: &str, params: impl Params) -> Result<Vec<Task<u64>>> {
         self.connection
             .prepare(query)?
-            .query_map(params, task_from_row)
-            .wrap_err("could not execute sql statement")
-            .with_context(|| query.to_owned())?
+            .query_map(params, task_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?
             .into_iter()
-            .collect::<Result<_, _>>()
-            .wrap_err("cannot convert tasks from sql statement")
-            .with_context(|| query.to_owned())
+            .map(|task| self.with_tags(task))
+            .collect()
+    }
+
+    fn with_tags(&self, mut task: Task<u64>) -> Result<Task<u64>> {
+        let tags = self
+            .connection
+            .prepare("SELECT tag FROM task_tags WHERE task_id=?1 ORDER BY tag")?
+            .query_map((task.id(),), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        MutTask::set_tags(&mut task, tags);
+        Ok(task)
     }
 
-    fn save(&self, task: &Task<impl DayRef>) -> eyre::Result<()> {
+    fn save(&self, task: &Task<impl DayRef>) -> Result<()> {
+        let before = self.journal.snapshot(task.id())?;
         self.connection.execute(
-            "UPDATE tasks SET day_id=?1, start=?2, end=?3, description=?4 WHERE id=?5",
+            "UPDATE tasks SET day_id=?1, start=?2, end=?3, description=?4, notes=?5 WHERE id=?6",
             (
                 task.day_id(),
                 task.start(),
                 task.end(),
                 task.description(),
+                task.notes(),
                 task.id(),
             ),
         )?;
+        self.connection
+            .execute("DELETE FROM task_tags WHERE task_id=?1", (task.id(),))?;
+        for tag in task.tags() {
+            self.connection.execute(
+                "INSERT INTO task_tags (task_id, tag) VALUES (?1, ?2)",
+                (task.id(), tag),
+            )?;
+        }
+        self.journal.record(task.id(), before)?;
         Ok(())
     }
 }
@@ -223,5 +803,18 @@ pub fn task_from_row(row: &Row) -> rusqlite::Result<Task<u64>> {
     let end = row.get("end")?;
     let description: String = row.get("description")?;
     let description = description.trim();
-    Ok(Task::new(id, day, start, end, description.to_owned()))
+    let notes: Option<String> = row.get("notes")?;
+    let mut task = Task::new(id, day, start, end, description.to_owned(), Vec::new());
+    MutTask::set_notes(&mut task, notes);
+    Ok(task)
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    tags.iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .sorted()
+        .dedup()
+        .collect()
 }
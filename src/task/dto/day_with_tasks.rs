@@ -1,28 +1,59 @@
 use chrono::TimeDelta;
 use itertools::Itertools;
-use serde::Serialize;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 
-use crate::day::Day;
+use crate::{
+    day::Day,
+    rounding::{Rounding, RoundingScope},
+};
 
 use super::{task_group::TaskGroup, Task};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub struct DayWithTasks {
     day: Day,
     tasks: Vec<Task<Day>>,
+    rounding: Rounding,
 }
 
 impl DayWithTasks {
     pub fn new(day: Day, tasks: Vec<Task<Day>>) -> Self {
-        Self { day, tasks }
+        Self {
+            day,
+            tasks,
+            rounding: Rounding::default(),
+        }
+    }
+
+    /// Rounds the totals of the day and its groups.
+    pub fn with_rounding(self, rounding: Rounding) -> Self {
+        Self { rounding, ..self }
     }
 
     pub fn is_empty(&self) -> bool {
         self.tasks.is_empty()
     }
 
+    /// The total of the day, rounded per task, group or day.
     pub fn delta(&self) -> TimeDelta {
-        self.tasks.iter().map(|task| task.delta()).sum()
+        match self.rounding.per {
+            RoundingScope::Task => self
+                .tasks
+                .iter()
+                .map(|task| self.rounding.round(task.delta_on(&self.day)))
+                .sum(),
+            RoundingScope::Group => self.task_groups().iter().map(TaskGroup::delta).sum(),
+            RoundingScope::Day => self.rounding.round(self.raw_delta()),
+        }
+    }
+
+    /// The total of the day without rounding.
+    pub fn raw_delta(&self) -> TimeDelta {
+        self.tasks.iter().map(|task| task.delta_on(&self.day)).sum()
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
     }
 
     pub fn day(&self) -> &Day {
@@ -33,14 +64,19 @@ impl DayWithTasks {
         self.tasks.iter()
     }
 
+    /// Groups the tasks by their description ignoring the case, a group has the
+    /// description of its first task.
     pub fn task_groups(&self) -> Vec<TaskGroup> {
         let mut groups: Vec<_> = self
             .tasks
             .iter()
             .cloned()
-            .into_group_map_by(|task| task.description().to_owned())
-            .into_iter()
-            .map(|(key, value)| TaskGroup::new(key, value))
+            .into_group_map_by(|task| task.description().to_lowercase())
+            .into_values()
+            .map(|tasks| {
+                TaskGroup::new(tasks[0].description().to_owned(), self.day, tasks)
+                    .with_rounding(self.rounding)
+            })
             .collect();
 
         groups.sort_by_key(|group| group.latest_time());
@@ -48,3 +84,18 @@ impl DayWithTasks {
         groups
     }
 }
+
+/// The totals are part of the output, both rounded and as tracked.
+impl Serialize for DayWithTasks {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DayWithTasks", 4)?;
+        state.serialize_field("day", &self.day)?;
+        state.serialize_field("tasks", &self.tasks)?;
+        state.serialize_field("total_minutes", &self.delta().num_minutes())?;
+        state.serialize_field("raw_total_minutes", &self.raw_delta().num_minutes())?;
+        state.end()
+    }
+}
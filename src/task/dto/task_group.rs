@@ -1,27 +1,47 @@
-use chrono::{NaiveTime, TimeDelta};
+use chrono::{NaiveDateTime, TimeDelta};
 
-use crate::day::Day;
+use crate::{
+    day::Day,
+    rounding::{Rounding, RoundingScope},
+};
 
 use super::Task;
 
 pub struct TaskGroup {
     description: String,
+    day: Day,
     tasks: Vec<Task<Day>>,
+    rounding: Rounding,
 }
 
 impl TaskGroup {
-    pub fn new(description: String, tasks: Vec<Task<Day>>) -> Self {
-        Self { description, tasks }
+    pub fn new(description: String, day: Day, tasks: Vec<Task<Day>>) -> Self {
+        Self {
+            description,
+            day,
+            tasks,
+            rounding: Rounding::default(),
+        }
     }
 
     pub fn from_task(task: Task<Day>) -> Self {
         let description = task.description().to_owned();
+        let day = task.day();
         let tasks = vec![task];
-        Self { description, tasks }
+        Self {
+            description,
+            day,
+            tasks,
+            rounding: Rounding::default(),
+        }
+    }
+
+    pub fn with_rounding(self, rounding: Rounding) -> Self {
+        Self { rounding, ..self }
     }
 
     pub fn add_task(&mut self, task: Task<Day>) -> bool {
-        if task.description() != self.description {
+        if task.description().to_lowercase() != self.description.to_lowercase() {
             return false;
         }
         self.tasks.push(task);
@@ -36,13 +56,31 @@ impl TaskGroup {
         self.tasks.iter()
     }
 
+    pub fn day(&self) -> &Day {
+        &self.day
+    }
+
+    /// The total of the group, rounding per day is left to the day.
     pub fn delta(&self) -> TimeDelta {
-        self.tasks.iter().map(|task| task.delta()).sum()
+        match self.rounding.per {
+            RoundingScope::Task => self
+                .tasks
+                .iter()
+                .map(|task| self.rounding.round(task.delta_on(&self.day)))
+                .sum(),
+            RoundingScope::Group => self.rounding.round(self.raw_delta()),
+            RoundingScope::Day => self.raw_delta(),
+        }
+    }
+
+    /// The total of the group without rounding.
+    pub fn raw_delta(&self) -> TimeDelta {
+        self.tasks.iter().map(|task| task.delta_on(&self.day)).sum()
     }
 
-    pub fn latest_time(&self) -> Option<NaiveTime> {
+    pub fn latest_time(&self) -> Option<NaiveDateTime> {
         self.tasks.iter().fold(None, |latest_time, task| {
-            let task_end = task.end_or_day_time();
+            let task_end = task.end_or_now();
             match latest_time {
                 Some(latest_time) => {
                     if task_end > latest_time {
@@ -1,19 +1,20 @@
 use std::fmt::Display;
 
-use chrono::{Local, NaiveTime, TimeDelta};
-use rusqlite::Row;
+use chrono::{Days, NaiveDateTime, NaiveTime, TimeDelta};
 use serde::Serialize;
 
-use crate::day::{Day, DayRef, DayReference};
+use crate::day::{Day, DayRef};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Task<DayRefImpl> {
     id: u64,
     #[serde(skip)]
     day: DayRefImpl,
-    start: NaiveTime,
-    end: Option<NaiveTime>,
+    start: NaiveDateTime,
+    end: Option<NaiveDateTime>,
     description: String,
+    notes: Option<String>,
+    tags: Vec<String>,
 }
 
 pub struct MutTask {}
@@ -27,14 +28,22 @@ impl<DayRefImpl> Task<DayRefImpl> {
         self.description.as_str()
     }
 
-    pub fn start(&self) -> NaiveTime {
+    pub fn start(&self) -> NaiveDateTime {
         self.start
     }
 
-    pub fn end(&self) -> Option<NaiveTime> {
+    pub fn end(&self) -> Option<NaiveDateTime> {
         self.end
     }
 
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_slice()
+    }
+
     pub fn is_active(&self) -> bool {
         self.end.is_none()
     }
@@ -47,17 +56,19 @@ where
     pub fn new(
         id: u64,
         day: DayRefImpl,
-        start: NaiveTime,
-        end: Option<NaiveTime>,
-        mut description: String,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+        description: String,
+        tags: Vec<String>,
     ) -> Self {
-        description.make_ascii_lowercase();
         Self {
             id,
             day,
             start,
             end,
             description,
+            notes: None,
+            tags,
         }
     }
 
@@ -71,6 +82,10 @@ impl Task<u64> {
         self.day
     }
 
+    pub fn with_day(self, day: Day) -> Task<Day> {
+        MutTask::with_day(self, day)
+    }
+
     pub fn delta(&self) -> Option<TimeDelta> {
         self.end.map(|end| end - self.start)
     }
@@ -81,12 +96,34 @@ impl Task<Day> {
         self.day
     }
 
-    pub fn end_or_day_time(&self) -> NaiveTime {
-        self.end.unwrap_or_else(|| self.day.time())
+    pub fn end_or_now(&self) -> NaiveDateTime {
+        self.end.unwrap_or_else(|| self.day.clock().now())
     }
 
     pub fn delta(&self) -> TimeDelta {
-        self.end_or_day_time() - self.start
+        self.end_or_now() - self.start
+    }
+
+    /// The part of the duration which lies within the given day.
+    pub fn delta_on(&self, day: &Day) -> TimeDelta {
+        let start = self.start.max(day.start());
+        let end = self.end_or_now().min(day.end());
+        (end - start).max(TimeDelta::zero())
+    }
+
+    /// The start time on the day of the task.
+    pub fn start_at(&self, time: NaiveTime) -> NaiveDateTime {
+        self.day.date().and_time(time)
+    }
+
+    /// The end time on the day of the task or the following day, if the time
+    /// lies before the start of the task.
+    pub fn end_at(&self, time: NaiveTime) -> NaiveDateTime {
+        let end = self.start.date().and_time(time);
+        if end <= self.start {
+            return end + Days::new(1);
+        }
+        end
     }
 }
 
@@ -99,16 +136,28 @@ impl<DayRefImpl> Display for Task<DayRefImpl> {
             self.id,
             self.start.format("%H:%M")
         )?;
-        let Some(end) = self.end else {
-            return write!(f, "...");
-        };
-        write!(f, "{}", end.format("%H:%M"))
+        match self.end {
+            Some(end) => write!(f, "{}", end.format("%H:%M"))?,
+            None => write!(f, "...")?,
+        }
+        if !self.tags.is_empty() {
+            write!(f, " tags={}", self.tags.join(","))?;
+        }
+        Ok(())
     }
 }
 
 impl MutTask {
     pub(crate) fn with_day(task: Task<u64>, day: Day) -> Task<Day> {
-        Task::new(task.id, day, task.start, task.end, task.description)
+        Task {
+            id: task.id,
+            day,
+            start: task.start,
+            end: task.end,
+            description: task.description,
+            notes: task.notes,
+            tags: task.tags,
+        }
     }
 
     pub(crate) fn set_description<DayRefImpl>(task: &mut Task<DayRefImpl>, description: &str) {
@@ -116,11 +165,23 @@ impl MutTask {
         task.description.push_str(description);
     }
 
-    pub(crate) fn set_start(task: &mut Task<impl DayRef>, time: NaiveTime) {
+    pub(crate) fn set_notes<DayRefImpl>(task: &mut Task<DayRefImpl>, notes: Option<String>) {
+        task.notes = notes;
+    }
+
+    pub(crate) fn set_tags<DayRefImpl>(task: &mut Task<DayRefImpl>, tags: Vec<String>) {
+        task.tags = tags;
+    }
+
+    pub(crate) fn set_start(task: &mut Task<impl DayRef>, time: NaiveDateTime) {
         task.start = time;
     }
 
-    pub(crate) fn set_end(task: &mut Task<impl DayRef>, time: NaiveTime) {
+    pub(crate) fn set_end(task: &mut Task<impl DayRef>, time: NaiveDateTime) {
         task.end = Some(time);
     }
+
+    pub(crate) fn clear_end(task: &mut Task<impl DayRef>) {
+        task.end = None;
+    }
 }
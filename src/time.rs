@@ -1,43 +1,243 @@
 use std::str::FromStr;
 
-use chrono::{NaiveTime, TimeDelta};
-use eyre::{eyre, ContextCompat};
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use serde::Serializer;
 
+use crate::{day::Day, task::Task, Error, Result};
+
+const DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// The longest duration of a delta, longer ones are most likely typos.
+const MAX_DELTA_MINUTES: i64 = 366 * 24 * 60;
+
+const EXAMPLES: &str = "e.g. 1030, 10:30, 10:30pm, +15m, -1h30m, now-10m or 'yesterday 17:00'";
+
+/// A point in time as given on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TimeOrDelta {
+    /// A time on the day of the task, e.g. `1030`, `10:30`, `10.30` or `10:30pm`.
     Time(NaiveTime),
+    /// A shift of the time which is changed, e.g. `+1h15m`, `-90m` or `+5` minutes.
     Delta(TimeDelta),
+    /// A time relative to now, e.g. `now` or `now-10m`.
+    Now(TimeDelta),
+    /// A time on a day relative to today, e.g. `yesterday 17:00`.
+    DayTime(i64, NaiveTime),
+    /// A date and time like `2024-05-01T17:00`.
+    DateTime(NaiveDateTime),
+}
+
+impl TimeOrDelta {
+    /// Resolves a time on the given date, a delta is relative to now.
+    pub fn datetime(&self, date: NaiveDate, now: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Self::Time(time) => date.and_time(*time),
+            Self::Delta(delta) => now + *delta,
+            _ => self.absolute(now),
+        }
+    }
+
+    /// Resolves a new start of the task, a time lies on the day of the task and
+    /// a delta shifts the start.
+    pub fn start_of(&self, task: &Task<Day>, now: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Self::Time(time) => task.start_at(*time),
+            Self::Delta(delta) => task.start() + *delta,
+            _ => self.absolute(now),
+        }
+    }
+
+    /// Resolves a new end of the task, a time lies after the start of the task
+    /// and a delta shifts the end or now for a running task.
+    pub fn end_of(&self, task: &Task<Day>, now: NaiveDateTime) -> NaiveDateTime {
+        self.end_after(task, task.start(), now)
+    }
+
+    /// Resolves a new end of the task like `end_of`, but a time lies after the
+    /// given new start of the task.
+    pub fn end_after(
+        &self,
+        task: &Task<Day>,
+        start: NaiveDateTime,
+        now: NaiveDateTime,
+    ) -> NaiveDateTime {
+        match self {
+            Self::Time(time) => match start.date().and_time(*time) {
+                end if end <= start => end + Days::new(1),
+                end => end,
+            },
+            Self::Delta(delta) => task.end().unwrap_or(now) + *delta,
+            _ => self.absolute(now),
+        }
+    }
+
+    fn absolute(&self, now: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Self::Time(time) => now.date().and_time(*time),
+            Self::Delta(delta) | Self::Now(delta) => now + *delta,
+            Self::DayTime(days, time) => {
+                let date = match *days < 0 {
+                    true => now.date() - Days::new(days.unsigned_abs()),
+                    false => now.date() + Days::new(days.unsigned_abs()),
+                };
+                date.and_time(*time)
+            }
+            Self::DateTime(datetime) => *datetime,
+        }
+    }
 }
 
 impl FromStr for TimeOrDelta {
-    type Err = eyre::Error;
-
-    fn from_str(s: &str) -> eyre::Result<Self> {
-        if is_digit(s) && matches!(s.len(), 1 | 2) {
-            let time = NaiveTime::from_hms_opt(u32::from_str(s)?, 0, 0)
-                .wrap_err("time values are not right")?;
-            return Ok(Self::Time(time));
-        }
-        if is_digit(s) && matches!(s.len(), 3 | 4) {
-            let split_index = s.len() - 2;
-            let (hours, minutes) = s.split_at(split_index);
-            let time = NaiveTime::from_hms_opt(u32::from_str(hours)?, u32::from_str(minutes)?, 0)
-                .wrap_err("time values are not right")?;
-            return Ok(Self::Time(time));
-        };
-        if s[..1] == *"+" {
-            let minutes = u32::from_str(&s[1..])?;
-            let delta = TimeDelta::minutes(minutes as i64);
-            return Ok(Self::Delta(delta));
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let value = s.trim().to_lowercase();
+        if value.is_empty() {
+            return Err(Error::InvalidTime(format!(
+                "the time is empty, use {}",
+                EXAMPLES
+            )));
+        }
+        if let Some(delta) = value.strip_prefix("now") {
+            return match delta.trim() {
+                "" => Ok(Self::Now(TimeDelta::zero())),
+                delta => parse_delta(delta).map(Self::Now),
+            };
         }
-        if s[..1] == *"-" {
-            let minutes = u32::from_str(&s[1..])?;
-            let delta = TimeDelta::minutes(-(minutes as i64));
-            return Ok(Self::Delta(delta));
+        if value.starts_with(['+', '-']) {
+            return parse_delta(&value).map(Self::Delta);
         }
-        Err(eyre!("could not convert string to time: {}", s))
+        for (day, days) in [("yesterday", -1), ("today", 0)] {
+            if let Some(time) = value.strip_prefix(day) {
+                return parse_time(time.trim()).map(|time| Self::DayTime(days, time));
+            }
+        }
+        if let Some(datetime) = DATETIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(&value.to_uppercase(), format).ok())
+        {
+            return Ok(Self::DateTime(datetime));
+        }
+        parse_time(&value).map(Self::Time)
+    }
+}
+
+/// Parses `+90`, `-90m`, `+1h15m` or `+2h`, plain numbers are minutes.
+fn parse_delta(s: &str) -> Result<TimeDelta> {
+    let (sign, value) = match (s.strip_prefix('+'), s.strip_prefix('-')) {
+        (Some(value), _) => (1, value.trim()),
+        (_, Some(value)) => (-1, value.trim()),
+        _ => return Err(invalid_time(s)),
+    };
+    if value.is_empty() {
+        return Err(Error::InvalidTime(format!(
+            "the duration of '{}' is missing, use {}",
+            s, EXAMPLES
+        )));
+    }
+    if is_digit(value) {
+        let minutes = check_delta(i64::from(number(value, s)?), s)?;
+        return Ok(TimeDelta::minutes(sign * minutes));
+    }
+    let mut minutes = 0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|char: char| !char.is_ascii_digit())
+            .ok_or_else(|| invalid_time(s))?;
+        let amount = i64::from(number(&rest[..digits], s)?);
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|char: char| char.is_ascii_digit())
+            .unwrap_or(rest.len());
+        minutes += match &rest[..unit] {
+            "h" => amount * 60,
+            "m" | "min" => amount,
+            unit => {
+                return Err(Error::InvalidTime(format!(
+                    "unknown unit '{}' in '{}', use h or m",
+                    unit, s
+                )))
+            }
+        };
+        minutes = check_delta(minutes, s)?;
+        rest = &rest[unit..];
+    }
+    Ok(TimeDelta::minutes(sign * minutes))
+}
+
+fn check_delta(minutes: i64, s: &str) -> Result<i64> {
+    match minutes <= MAX_DELTA_MINUTES {
+        true => Ok(minutes),
+        false => Err(Error::InvalidTime(format!(
+            "the duration of '{}' is longer than a year",
+            s
+        ))),
     }
 }
 
+/// Parses `10`, `1030`, `10:30`, `10.30` with an optional `am` or `pm`.
+fn parse_time(s: &str) -> Result<NaiveTime> {
+    let (value, meridiem) = match (s.strip_suffix("am"), s.strip_suffix("pm")) {
+        (Some(value), _) => (value.trim_end(), Some(0)),
+        (_, Some(value)) => (value.trim_end(), Some(12)),
+        _ => (s, None),
+    };
+    let (hours, minutes) = match value.split_once([':', '.']) {
+        Some((hours, minutes)) if minutes.len() == 2 => (hours, minutes),
+        Some(_) => return Err(invalid_time(s)),
+        None if is_digit(value) && matches!(value.len(), 1 | 2) => (value, "0"),
+        None if is_digit(value) && matches!(value.len(), 3 | 4) => value.split_at(value.len() - 2),
+        None => return Err(invalid_time(s)),
+    };
+    let (mut hours, minutes) = (number(hours, s)?, number(minutes, s)?);
+    if let Some(offset) = meridiem {
+        if !(1..=12).contains(&hours) {
+            return Err(Error::InvalidTime(format!(
+                "the hour of '{}' must be between 1 and 12",
+                s
+            )));
+        }
+        hours = hours % 12 + offset;
+    }
+    NaiveTime::from_hms_opt(hours, minutes, 0).ok_or_else(|| {
+        Error::InvalidTime(format!(
+            "'{}' is out of range, the hour must be below 24 and the minute below 60",
+            s
+        ))
+    })
+}
+
+fn number(s: &str, value: &str) -> Result<u32> {
+    match is_digit(s) && !s.is_empty() {
+        true => u32::from_str(s).map_err(|_| invalid_time(value)),
+        false => Err(invalid_time(value)),
+    }
+}
+
+fn invalid_time(s: &str) -> Error {
+    Error::InvalidTime(format!(
+        "could not convert '{}' to a time, use {}",
+        s, EXAMPLES
+    ))
+}
+
 fn is_digit(s: &str) -> bool {
-    s.chars().all(|char| matches!(char, '0'..='9'))
+    s.chars().all(|char| char.is_ascii_digit())
+}
+
+/// Serializes a duration as whole minutes.
+pub fn serialize_minutes<S>(
+    delta: &TimeDelta,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_i64(delta.num_minutes())
 }
@@ -0,0 +1,133 @@
+use std::{env, fs, path::PathBuf, process};
+
+use chrono::{TimeDelta, Weekday};
+use ttrace::{
+    rounding::{Rounding, RoundingMode, RoundingScope},
+    Config, Error,
+};
+
+fn file(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("ttrace-config-{}", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    directory.join(name)
+}
+
+#[test]
+fn reads_a_toml_config() {
+    let path = file("read.toml");
+    fs::write(
+        &path,
+        r#"
+# the database lives in the home directory
+path = "~/ttrace"
+week_start = "sunday"
+default_tags = ["work", "acme # not a comment"]
+
+[rounding]
+increment = 15 # minutes
+mode = "up"
+per = "task"
+"#,
+    )
+    .unwrap();
+    let config = Config::read(&path).unwrap();
+    assert!(!config.path().starts_with("~"));
+    assert!(config.path().ends_with("ttrace"));
+    assert_eq!(config.week_start(), Weekday::Sun);
+    assert_eq!(config.time_format(), "%H:%M");
+    assert_eq!(config.default_tags(), ["work", "acme # not a comment"]);
+    assert_eq!(
+        config.rounding(),
+        Rounding::new(15, RoundingMode::Up).with_scope(RoundingScope::Task)
+    );
+}
+
+#[test]
+fn reads_multi_line_arrays_and_inline_tables() {
+    let path = file("syntax.toml");
+    fs::write(
+        &path,
+        r#"
+default_tags = [
+    "work",
+    'acme',
+]
+rounding = { increment = 6, mode = "down" }
+"#,
+    )
+    .unwrap();
+    let config = Config::read(&path).unwrap();
+    assert_eq!(config.default_tags(), ["work", "acme"]);
+    assert_eq!(config.rounding(), Rounding::new(6, RoundingMode::Down));
+}
+
+#[test]
+fn reads_a_json_config() {
+    let path = file("read.json");
+    fs::write(
+        &path,
+        r#"{"path": "/tmp/ttrace", "time_format": "%I:%M %p"}"#,
+    )
+    .unwrap();
+    let config = Config::read(&path).unwrap();
+    assert_eq!(config.path(), PathBuf::from("/tmp/ttrace"));
+    assert_eq!(config.time_format(), "%I:%M %p");
+    assert_eq!(config.week_start(), Weekday::Mon);
+}
+
+#[test]
+fn writes_the_settings_which_were_set() {
+    let path = file("write.toml");
+    let mut config = Config::default();
+    config.set("week_start", "tue").unwrap();
+    config.set("default_tags", "work, acme").unwrap();
+    config.set("rounding.increment", "6").unwrap();
+    config.write(&path).unwrap();
+
+    let config = Config::read(&path).unwrap();
+    assert_eq!(config.week_start(), Weekday::Tue);
+    assert_eq!(config.default_tags(), ["work", "acme"]);
+    assert_eq!(config.rounding().increment, 6);
+}
+
+#[test]
+fn rejects_unknown_and_invalid_settings() {
+    let mut config = Config::default();
+    assert!(matches!(config.set("week", "mon"), Err(Error::Config(_))));
+    assert!(matches!(
+        config.set("rounding", "up"),
+        Err(Error::Config(_))
+    ));
+    assert!(matches!(
+        config.set("rounding.increment", "soon"),
+        Err(Error::Config(_))
+    ));
+    assert!(matches!(
+        config.set("time_format", "%Q"),
+        Err(Error::Config(_))
+    ));
+    assert!(matches!(
+        config.set("time_format", "%H:%M %z"),
+        Err(Error::Config(_))
+    ));
+    assert_eq!(config.week_start(), Weekday::Mon);
+}
+
+#[test]
+fn reports_the_line_of_an_invalid_toml_config() {
+    let path = file("invalid.toml");
+    fs::write(&path, "week_start = \"mon\"\ndefault_tags = [\n").unwrap();
+    let error = Config::read(&path).unwrap_err();
+    assert!(error.to_string().contains("line 2"), "{}", error);
+    assert_eq!(error.exit_code(), 7);
+}
+
+#[test]
+fn rounds_durations_to_the_increment() {
+    let delta = TimeDelta::minutes(67);
+    assert_eq!(Rounding::default().round(delta), delta);
+    let round = |mode| Rounding::new(15, mode).round(delta).num_minutes();
+    assert_eq!(round(RoundingMode::Up), 75);
+    assert_eq!(round(RoundingMode::Down), 60);
+    assert_eq!(round(RoundingMode::Nearest), 60);
+}
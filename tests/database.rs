@@ -0,0 +1,115 @@
+use std::{env, fs, process};
+
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use rusqlite::Connection;
+use ttrace::{
+    database::latest_version, open_database_connection, Clock, Config, Error, JournalRepository,
+    TaskRepository,
+};
+
+/// A config with its own empty directory for the database.
+fn config(name: &str) -> Config {
+    let directory = env::temp_dir().join(format!("ttrace-database-{}-{}", name, process::id()));
+    _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("config.toml");
+    fs::write(&path, format!("path = {:?}\n", directory.to_string_lossy())).unwrap();
+    Config::read(&path).unwrap()
+}
+
+fn datetime(date: &str, time: &str) -> NaiveDateTime {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .unwrap()
+        .and_time(time.parse().unwrap())
+}
+
+fn user_version(connection: &Connection) -> u32 {
+    connection
+        .query_row("PRAGMA user_version", (), |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn migrates_times_of_day_to_timestamps() {
+    let config = config("times");
+    let old = Connection::open(config.database_path()).unwrap();
+    old.execute_batch(
+        "CREATE TABLE days (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date DATE NOT NULL
+        );
+        CREATE TABLE tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            day_id INTEGER NOT NULL,
+            start DATE NOT NULL,
+            end DATE,
+            description TEXT NOT NULL
+        );
+        INSERT INTO days (id, date) VALUES (1, '2024-04-30'), (2, '2024-05-01');
+        INSERT INTO tasks (id, day_id, start, end, description) VALUES
+            (1, 1, '09:00:00.000', '10:30:15.500', 'review'),
+            (2, 1, '11:00:00.000', null, 'forgotten'),
+            (3, 2, '08:00:00.250', null, 'write tests');",
+    )
+    .unwrap();
+    drop(old);
+
+    // The clock lies before the wall clock, the task of its day keeps running.
+    let clock = Clock::Fixed(datetime("2024-05-01", "09:00:00"));
+    let connection = open_database_connection(&config, clock).unwrap();
+    assert!(config.backup_path(0).exists());
+
+    let rows: Vec<(String, Option<String>)> = connection
+        .prepare("SELECT start, end FROM tasks ORDER BY id")
+        .unwrap()
+        .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        [
+            (
+                "2024-04-30 09:00:00.000".to_owned(),
+                Some("2024-04-30 10:30:15.500".to_owned())
+            ),
+            (
+                "2024-04-30 11:00:00.000".to_owned(),
+                Some("2024-04-30 23:59:59".to_owned())
+            ),
+            ("2024-05-01 08:00:00.250".to_owned(), None),
+        ]
+    );
+
+    assert_eq!(user_version(&connection), latest_version());
+
+    let journal = JournalRepository::new(connection.clone(), clock).into();
+    let tasks = TaskRepository::new(connection, journal, clock);
+    let current = tasks.current().unwrap();
+    assert_eq!(current.description(), "write tests");
+    assert_eq!(
+        current.start(),
+        datetime("2024-05-01", "08:00:00") + TimeDelta::milliseconds(250)
+    );
+}
+
+#[test]
+fn refuses_databases_of_newer_versions() {
+    let config = config("newer");
+    let clock = Clock::Fixed(datetime("2024-05-01", "09:00:00"));
+    let connection = open_database_connection(&config, clock).unwrap();
+    let latest = latest_version();
+    connection
+        .pragma_update(None, "user_version", latest + 1)
+        .unwrap();
+    drop(connection);
+
+    let error = open_database_connection(&config, clock).unwrap_err();
+    assert!(matches!(
+        error,
+        Error::UnsupportedDatabaseVersion { version, supported }
+            if version == latest + 1 && supported == latest
+    ));
+    assert_eq!(error.exit_code(), 8);
+    assert!(!config.backup_path(latest).exists());
+}
@@ -0,0 +1,107 @@
+use std::{env, rc::Rc};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use ttrace::{
+    database::open_in_memory_database,
+    import::{import, parse_timewarrior, parse_toggl, ImportEntry},
+    Clock, DayRepository, JournalRepository, TaskRepository,
+};
+
+const TIMEWARRIOR: &str = r#"[
+    {"id": 3, "start": "20240501T070000Z", "end": "20240501T080000Z",
+     "tags": ["ttrace", "review"], "annotation": "review pull requests"},
+    {"id": 2, "start": "20240501T080000Z", "end": "20240501T083000Z", "tags": ["meeting"]},
+    {"id": 1, "start": "20240501T090000Z", "tags": ["support"]}
+]"#;
+
+const TOGGL: &str = "\
+User,Email,Client,Project,Description,Billable,Start date,Start time,End date,End time,Duration,Tags
+Jane,jane@example.com,,ttrace,write docs,No,2024-05-01,13:00:00,2024-05-01,14:15:00,01:15:00,\"docs, writing\"
+Jane,jane@example.com,,,lunch,No,2024-05-01,12:00:00,2024-05-01,12:30:00,00:30:00,
+";
+
+fn datetime(time: &str) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 5, 1)
+        .unwrap()
+        .and_time(time.parse().unwrap())
+}
+
+/// The timewarrior entries in a timezone two hours ahead of UTC.
+fn timewarrior_entries() -> Vec<ImportEntry> {
+    env::set_var("TZ", "XYZ-2");
+    parse_timewarrior(TIMEWARRIOR.as_bytes()).unwrap()
+}
+
+fn repositories() -> (Rc<JournalRepository>, DayRepository, TaskRepository) {
+    let connection = open_in_memory_database().unwrap();
+    let clock = Clock::Fixed(datetime("18:00:00"));
+    let journal = Rc::new(JournalRepository::new(connection.clone(), clock));
+    let days = DayRepository::new(connection.clone(), clock);
+    let tasks = TaskRepository::new(connection, journal.clone(), clock);
+    (journal, days, tasks)
+}
+
+#[test]
+fn timewarrior_times_are_converted_from_utc_to_local_time() {
+    let entries = timewarrior_entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].start(), datetime("09:00:00"));
+    assert_eq!(entries[0].end(), Some(datetime("10:00:00")));
+    assert_eq!(entries[0].description(), "review pull requests");
+    assert_eq!(entries[0].tags(), ["ttrace", "review"]);
+    // without an annotation, the tags are the description
+    assert_eq!(entries[1].description(), "meeting");
+    assert_eq!(entries[2].end(), None);
+}
+
+#[test]
+fn toggl_projects_are_added_to_the_tags() {
+    let entries = parse_toggl(TOGGL.as_bytes()).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].start(), datetime("13:00:00"));
+    assert_eq!(entries[0].end(), Some(datetime("14:15:00")));
+    assert_eq!(entries[0].description(), "write docs");
+    assert_eq!(entries[0].tags(), ["docs", "writing", "ttrace"]);
+    assert!(entries[1].tags().is_empty());
+}
+
+#[test]
+fn invalid_exports_fail_to_parse() {
+    assert!(parse_timewarrior("[{\"start\": \"2024-05-01\"}]".as_bytes()).is_err());
+    assert!(parse_toggl("Description,Start date\nlunch,today\n".as_bytes()).is_err());
+}
+
+#[test]
+fn import_skips_open_intervals_and_detects_duplicates() {
+    let (_, days, tasks) = repositories();
+    let summary = import(timewarrior_entries(), false, &days, &tasks).unwrap();
+    assert_eq!(summary.imported().count(), 2);
+    let skipped: Vec<_> = summary.skipped().map(|skipped| skipped.reason()).collect();
+    assert_eq!(skipped, ["the task is not finished"]);
+
+    let summary = import(timewarrior_entries(), false, &days, &tasks).unwrap();
+    assert_eq!(summary.imported().count(), 0);
+    assert_eq!(summary.duplicates().count(), 2);
+
+    let mut entries = parse_toggl(TOGGL.as_bytes()).unwrap();
+    entries.extend(timewarrior_entries().into_iter().take(1));
+    let summary = import(entries, false, &days, &tasks).unwrap();
+    assert_eq!(summary.imported().count(), 2);
+    assert_eq!(summary.duplicates().count(), 1);
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(day.tasks().count(), 4);
+}
+
+#[test]
+fn dry_run_leaves_the_database_unchanged() {
+    let (journal, days, tasks) = repositories();
+    // like the import command, the changes of a dry run are rolled back
+    journal.begin("import --dry-run").unwrap();
+    let summary = import(timewarrior_entries(), true, &days, &tasks).unwrap();
+    journal.rollback().unwrap();
+
+    assert!(summary.is_dry_run());
+    assert_eq!(summary.imported().count(), 2);
+    assert!(days.find(datetime("09:00:00").date()).unwrap().is_none());
+    assert!(journal.history(10).unwrap().is_empty());
+}
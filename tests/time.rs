@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use ttrace::{Error, TimeOrDelta};
+
+fn parse(s: &str) -> TimeOrDelta {
+    TimeOrDelta::from_str(s).unwrap()
+}
+
+fn time(hours: u32, minutes: u32) -> TimeOrDelta {
+    TimeOrDelta::Time(NaiveTime::from_hms_opt(hours, minutes, 0).unwrap())
+}
+
+fn now() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 5, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+}
+
+#[test]
+fn parses_times_of_day() {
+    assert_eq!(parse("9"), time(9, 0));
+    assert_eq!(parse("930"), time(9, 30));
+    assert_eq!(parse("1030"), time(10, 30));
+    assert_eq!(parse("10:30"), time(10, 30));
+    assert_eq!(parse("10.30"), time(10, 30));
+    assert_eq!(parse("10:30pm"), time(22, 30));
+    assert_eq!(parse("10 PM"), time(22, 0));
+    assert_eq!(parse("12am"), time(0, 0));
+    assert_eq!(parse("12:15pm"), time(12, 15));
+}
+
+#[test]
+fn parses_deltas() {
+    assert_eq!(parse("+5"), TimeOrDelta::Delta(TimeDelta::minutes(5)));
+    assert_eq!(parse("-90m"), TimeOrDelta::Delta(TimeDelta::minutes(-90)));
+    assert_eq!(parse("+1h15m"), TimeOrDelta::Delta(TimeDelta::minutes(75)));
+    assert_eq!(parse("-2h"), TimeOrDelta::Delta(TimeDelta::hours(-2)));
+}
+
+#[test]
+fn resolves_relative_and_absolute_times() {
+    let date = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+    let resolve = |s: &str| parse(s).datetime(date, now());
+    assert_eq!(resolve("now"), now());
+    assert_eq!(resolve("now-10m"), now() - TimeDelta::minutes(10));
+    assert_eq!(resolve("+15m"), now() + TimeDelta::minutes(15));
+    assert_eq!(resolve("10:30"), date.and_hms_opt(10, 30, 0).unwrap());
+    assert_eq!(
+        resolve("yesterday 17:00"),
+        date.and_hms_opt(17, 0, 0).unwrap()
+    );
+    assert_eq!(
+        resolve("2024-03-01T08:15"),
+        NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(8, 15, 0)
+            .unwrap()
+    );
+}
+
+#[test]
+fn rejects_invalid_times() {
+    for value in [
+        "",
+        " ",
+        "+",
+        "-",
+        "25:00",
+        "10:75",
+        "13pm",
+        "10:3",
+        "+1x",
+        "now 5",
+        "é",
+        "+4294967295h",
+        "-9000h",
+        "now+527041",
+        "+8000h8000h",
+    ] {
+        assert!(
+            matches!(TimeOrDelta::from_str(value), Err(Error::InvalidTime(_))),
+            "{:?}",
+            value
+        );
+    }
+}
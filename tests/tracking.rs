@@ -0,0 +1,885 @@
+use std::rc::Rc;
+
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta, Weekday};
+use rusqlite::Connection;
+use ttrace::{
+    check::{check, IssueKind},
+    database::open_in_memory_database,
+    export::write_csv,
+    report::{Report, ReportGrouping},
+    rounding::{Rounding, RoundingMode, RoundingScope},
+    status::Status,
+    summary::Summary,
+    Clock, DayRepository, Error, JournalRepository, TaskRepository,
+};
+
+struct Tracker {
+    connection: Rc<Connection>,
+    journal: Rc<JournalRepository>,
+}
+
+impl Tracker {
+    fn new() -> Self {
+        let connection = open_in_memory_database().unwrap();
+        let journal = Rc::new(JournalRepository::new(connection.clone(), Clock::System));
+        Self {
+            connection,
+            journal,
+        }
+    }
+
+    /// The repositories as seen at the given time on 2024-05-01.
+    fn at(&self, time: &str) -> (DayRepository, TaskRepository) {
+        let clock = Clock::Fixed(datetime(time));
+        let days = DayRepository::new(self.connection.clone(), clock);
+        let tasks = TaskRepository::new(self.connection.clone(), self.journal.clone(), clock);
+        (days, tasks)
+    }
+
+    /// Runs the changes as one operation of the journal, like a command does.
+    fn operation<T>(&self, command: &str, change: impl FnOnce() -> T) -> T {
+        self.journal.begin(command).unwrap();
+        let result = change();
+        self.journal.commit().unwrap();
+        result
+    }
+
+    fn start(&self, time: &str, description: &str) {
+        let (days, tasks) = self.at(time);
+        tasks
+            .start(days.today().unwrap(), description, &[])
+            .unwrap();
+    }
+}
+
+fn datetime(time: &str) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 5, 1)
+        .unwrap()
+        .and_time(time.parse().unwrap())
+}
+
+#[test]
+fn start_uses_the_time_of_the_clock() {
+    let tracker = Tracker::new();
+    let (days, tasks) = tracker.at("09:00:00");
+    let today = days.today().unwrap();
+    assert_eq!(today.date(), datetime("09:00:00").date());
+    assert!(today.is_today());
+
+    let task = tasks
+        .start(today, "write tests", &["ttrace".to_owned()])
+        .unwrap();
+    assert_eq!(task.start(), datetime("09:00:00"));
+    assert_eq!(task.end(), None);
+    assert_eq!(task.tags(), ["ttrace"]);
+}
+
+#[test]
+fn running_task_lasts_until_now() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+
+    let (days, tasks) = tracker.at("10:30:00");
+    let task = tasks.current().unwrap();
+    assert_eq!(task.delta(), TimeDelta::minutes(90));
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(day.delta(), TimeDelta::minutes(90));
+}
+
+#[test]
+fn start_stops_the_running_task() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+
+    let (days, tasks) = tracker.at("10:00:00");
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    let day_tasks: Vec<_> = day.tasks().collect();
+    let [first, second] = day_tasks.as_slice() else {
+        panic!("expected two tasks, got {:?}", day_tasks);
+    };
+    assert_eq!(first.end(), Some(datetime("10:00:00")));
+    assert_eq!(second.start(), datetime("10:00:00"));
+    assert!(second.is_active());
+}
+
+#[test]
+fn stop_ends_the_running_task_now() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+
+    let (_, tasks) = tracker.at("11:15:00");
+    let task = tasks.stop().unwrap();
+    assert_eq!(task.end(), Some(datetime("11:15:00")));
+    assert_eq!(task.delta(), TimeDelta::minutes(135));
+    assert!(matches!(tasks.current(), Err(Error::NoActiveTask)));
+}
+
+#[test]
+fn stop_without_a_running_task_fails() {
+    let tracker = Tracker::new();
+    let (_, tasks) = tracker.at("09:00:00");
+    let error = tasks.stop().unwrap_err();
+    assert!(matches!(error, Error::NoActiveTask));
+    assert_eq!(error.exit_code(), 3);
+}
+
+#[test]
+fn restart_at_a_time_moves_the_end_of_the_previous_task() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+
+    let (days, tasks) = tracker.at("10:30:00");
+    let current = tasks.current().unwrap();
+    let time = current.start_at("09:45:00".parse().unwrap());
+    let task = tasks.set_start(current, time).unwrap();
+    assert_eq!(task.start(), datetime("09:45:00"));
+
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(
+        day.tasks().next().unwrap().end(),
+        Some(datetime("09:45:00"))
+    );
+    assert_eq!(day.delta(), TimeDelta::minutes(90));
+}
+
+#[test]
+fn restart_by_a_delta_keeps_the_tasks_adjacent() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+
+    let (days, tasks) = tracker.at("10:30:00");
+    let current = tasks.current().unwrap();
+    let task = tasks.shift_start(current, TimeDelta::minutes(10)).unwrap();
+    assert_eq!(task.start(), datetime("10:10:00"));
+
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(
+        day.tasks().next().unwrap().end(),
+        Some(datetime("10:10:00"))
+    );
+}
+
+#[test]
+fn restart_past_the_start_of_the_previous_task_fails() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+
+    let (_, tasks) = tracker.at("10:30:00");
+    let current = tasks.current().unwrap();
+    let time = current.start_at("08:30:00".parse().unwrap());
+    let error = tasks.set_start(current, time).unwrap_err();
+    assert!(matches!(error, Error::InvalidTime(_)));
+}
+
+#[test]
+fn edit_moves_a_task_past_its_old_end() {
+    let tracker = Tracker::new();
+    let (days, tasks) = tracker.at("09:00:00");
+    let today = days.today().unwrap();
+    tasks
+        .add(
+            today,
+            datetime("06:00:00"),
+            datetime("06:30:00"),
+            "standup",
+            &[],
+        )
+        .unwrap();
+    let task = tasks.last().unwrap();
+
+    let error = tasks
+        .set_start(task.clone(), datetime("06:45:00"))
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidTime(_)));
+
+    let task = tasks
+        .set_range(task, datetime("06:45:00"), datetime("07:00:00"))
+        .unwrap();
+    assert_eq!(task.start(), datetime("06:45:00"));
+    assert_eq!(task.end(), Some(datetime("07:00:00")));
+
+    let error = tasks
+        .set_range(task, datetime("07:30:00"), datetime("07:15:00"))
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidTime(_)));
+}
+
+#[test]
+fn rename_changes_the_running_task() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+
+    let (_, tasks) = tracker.at("09:30:00");
+    let task = tasks.rename_current("write more tests").unwrap();
+    assert_eq!(task.description(), "write more tests");
+    assert_eq!(tasks.current().unwrap().description(), "write more tests");
+}
+
+#[test]
+fn rename_without_a_running_task_fails() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    let (_, tasks) = tracker.at("10:00:00");
+    tasks.stop().unwrap();
+
+    let error = tasks.rename_current("review").unwrap_err();
+    assert!(matches!(error, Error::NoActiveTask));
+}
+
+#[test]
+fn restore_a_running_task_while_another_runs_fails() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    let (_, tasks) = tracker.at("09:30:00");
+    let deleted = tasks.delete(tasks.current().unwrap()).unwrap();
+    tracker.start("10:00:00", "review");
+
+    let (_, tasks) = tracker.at("11:00:00");
+    assert!(matches!(
+        tasks.restore(deleted.clone()),
+        Err(Error::Overlap(_))
+    ));
+    tasks.stop().unwrap();
+    assert!(matches!(tasks.restore(deleted), Err(Error::Overlap(_))));
+    assert!(matches!(tasks.current(), Err(Error::NoActiveTask)));
+}
+
+#[test]
+fn undo_reverts_a_start_including_the_stopped_task() {
+    let tracker = Tracker::new();
+    tracker.operation("start", || tracker.start("09:00:00", "write tests"));
+    tracker.operation("start", || tracker.start("10:00:00", "review"));
+
+    let (days, tasks) = tracker.at("11:00:00");
+    let operation = tracker.journal.undo().unwrap().unwrap();
+    assert_eq!(operation.command(), "start");
+    let task = tasks.current().unwrap();
+    assert_eq!(task.description(), "write tests");
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(day.tasks().count(), 1);
+
+    tracker.journal.redo().unwrap().unwrap();
+    let task = tasks.current().unwrap();
+    assert_eq!(task.description(), "review");
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    let ends: Vec<_> = day.tasks().map(|task| task.end()).collect();
+    assert_eq!(ends, [Some(datetime("10:00:00")), None]);
+    assert!(tracker.journal.redo().unwrap().is_none());
+}
+
+#[test]
+fn a_new_operation_after_undo_drops_the_undone_operation() {
+    let tracker = Tracker::new();
+    tracker.operation("start", || tracker.start("09:00:00", "write tests"));
+    tracker.operation("start", || tracker.start("10:00:00", "review"));
+    tracker.journal.undo().unwrap().unwrap();
+    tracker.operation("start", || tracker.start("10:30:00", "write docs"));
+
+    assert!(tracker.journal.redo().unwrap().is_none());
+    let history = tracker.journal.history(10).unwrap();
+    let commands: Vec<_> = history
+        .iter()
+        .map(|operation| operation.command())
+        .collect();
+    assert_eq!(commands, ["start", "start"]);
+    assert!(history.iter().all(|operation| !operation.is_undone()));
+}
+
+#[test]
+fn an_operation_without_changes_keeps_the_undone_operation() {
+    let tracker = Tracker::new();
+    tracker.operation("start", || tracker.start("09:00:00", "write tests"));
+    tracker.journal.undo().unwrap().unwrap();
+    let (days, tasks) = tracker.at("10:00:00");
+    tracker.operation("check", || check(&days, &tasks, false).unwrap());
+
+    let operation = tracker.journal.redo().unwrap().unwrap();
+    assert_eq!(operation.command(), "start");
+    assert_eq!(tasks.current().unwrap().description(), "write tests");
+}
+
+#[test]
+fn undo_and_redo_delete_and_restore() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    let (_, tasks) = tracker.at("10:00:00");
+    let task = tasks.stop().unwrap();
+    let id = task.id();
+
+    tracker.operation("delete", || tasks.delete(task).unwrap());
+    assert!(matches!(tasks.task(id), Err(Error::NotFound(_))));
+    tracker.journal.undo().unwrap().unwrap();
+    assert_eq!(tasks.task(id).unwrap().description(), "write tests");
+    tracker.journal.redo().unwrap().unwrap();
+    assert!(matches!(tasks.task(id), Err(Error::NotFound(_))));
+
+    let deleted = tasks.resolve(tasks.deleted_task(id).unwrap()).unwrap();
+    tracker.operation("restore", || tasks.restore(deleted).unwrap());
+    assert!(tasks.task(id).is_ok());
+    tracker.journal.undo().unwrap().unwrap();
+    assert!(matches!(tasks.task(id), Err(Error::NotFound(_))));
+
+    let history = tracker.journal.history(10).unwrap();
+    let states: Vec<_> = history
+        .iter()
+        .map(|operation| (operation.command(), operation.is_undone()))
+        .collect();
+    assert_eq!(states, [("restore", true), ("delete", false)]);
+}
+
+#[test]
+fn resume_starts_the_paused_task_again() {
+    let tracker = Tracker::new();
+    let (days, tasks) = tracker.at("09:00:00");
+    let tags = ["ttrace".to_owned()];
+    tasks
+        .start(days.today().unwrap(), "write tests", &tags)
+        .unwrap();
+
+    let (_, tasks) = tracker.at("12:00:00");
+    let paused = tasks.pause().unwrap();
+    assert_eq!(paused.end(), Some(datetime("12:00:00")));
+    assert!(matches!(tasks.current(), Err(Error::NoActiveTask)));
+
+    let (days, tasks) = tracker.at("13:00:00");
+    let resumed = tasks.resume(days.today().unwrap()).unwrap();
+    assert_ne!(resumed.id(), paused.id());
+    assert_eq!(resumed.start(), datetime("13:00:00"));
+    assert_eq!(resumed.description(), "write tests");
+    assert_eq!(resumed.tags(), tags);
+
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(day.task_groups().len(), 1);
+}
+
+#[test]
+fn resume_skips_tasks_added_during_the_pause() {
+    let tracker = Tracker::new();
+    let (days, tasks) = tracker.at("09:00:00");
+    let tags = ["acme".to_owned()];
+    tasks.start(days.today().unwrap(), "work", &tags).unwrap();
+
+    let (days, tasks) = tracker.at("12:00:00");
+    tasks.pause().unwrap();
+    tasks
+        .add(
+            days.today().unwrap(),
+            datetime("12:00:00"),
+            datetime("12:45:00"),
+            "lunch",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(tasks.last().unwrap().description(), "lunch");
+
+    let (days, tasks) = tracker.at("13:00:00");
+    let resumed = tasks.resume(days.today().unwrap()).unwrap();
+    assert_eq!(resumed.description(), "work");
+    assert_eq!(resumed.tags(), tags);
+    assert!(tasks.paused().unwrap().is_none());
+
+    // Without a paused task the task which ended last is resumed.
+    let (days, tasks) = tracker.at("14:00:00");
+    tasks.stop().unwrap();
+    tasks
+        .add(
+            days.today().unwrap(),
+            datetime("14:00:00"),
+            datetime("14:30:00"),
+            "review",
+            &[],
+        )
+        .unwrap();
+    let (days, tasks) = tracker.at("15:00:00");
+    let resumed = tasks.resume(days.today().unwrap()).unwrap();
+    assert_eq!(resumed.description(), "review");
+}
+
+#[test]
+fn resume_while_a_task_is_running_fails() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    let (days, tasks) = tracker.at("10:00:00");
+    let error = tasks.resume(days.today().unwrap()).unwrap_err();
+    assert!(matches!(error, Error::InvalidInput(_)));
+}
+
+#[test]
+fn resume_fails_if_the_running_task_cannot_be_read() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+    tracker
+        .connection
+        .execute("UPDATE tasks SET day_id = 99 WHERE end IS null", ())
+        .unwrap();
+    let (days, tasks) = tracker.at("11:00:00");
+    let error = tasks.resume(days.today().unwrap()).unwrap_err();
+    assert_eq!(error.kind(), "database");
+    let count: u32 = tracker
+        .connection
+        .query_row("SELECT count(*) FROM tasks WHERE end IS null", (), |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn continue_restarts_an_earlier_task() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+
+    let (days, tasks) = tracker.at("11:00:00");
+    let today = days.today().unwrap();
+    let earlier = tasks.day_with_tasks(today).unwrap();
+    let earlier = earlier.tasks().next().unwrap();
+    let task = tasks.continue_task(today, earlier).unwrap();
+    assert_eq!(task.description(), "write tests");
+    assert_eq!(task.start(), datetime("11:00:00"));
+    assert_eq!(tasks.last().unwrap().description(), "review");
+}
+
+#[test]
+fn descriptions_keep_their_case_but_are_grouped_ignoring_it() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "Fix ABC-123");
+    tracker.start("10:00:00", "review");
+    tracker.start("11:00:00", "fix abc-123");
+
+    let (days, tasks) = tracker.at("12:00:00");
+    assert_eq!(tasks.last().unwrap().description(), "review");
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    let groups = day.task_groups();
+    assert_eq!(groups.len(), 2);
+    let group = groups
+        .iter()
+        .find(|group| group.tasks().count() == 2)
+        .unwrap();
+    assert_eq!(group.description(), "Fix ABC-123");
+    assert_eq!(group.delta(), TimeDelta::hours(2));
+}
+
+#[test]
+fn notes_are_appended_to_the_running_task() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+
+    let (_, tasks) = tracker.at("09:30:00");
+    let task = tasks.current().unwrap();
+    assert_eq!(task.notes(), None);
+    let task = tasks.add_note(task, "cover the notes").unwrap();
+    tasks.add_note(task, "and the grouping").unwrap();
+    assert_eq!(
+        tasks.current().unwrap().notes(),
+        Some("cover the notes\nand the grouping")
+    );
+    let task = tasks.current().unwrap();
+    assert!(matches!(
+        tasks.add_note(task, " "),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn status_renders_the_running_task() {
+    let tracker = Tracker::new();
+    tracker.start("08:00:00", "review");
+    let (days, tasks) = tracker.at("09:00:00");
+    tasks
+        .start(days.today().unwrap(), "Write docs", &["docs".to_owned()])
+        .unwrap();
+
+    let (days, tasks) = tracker.at("09:45:00");
+    let today = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    let status = Status::new(Some(tasks.current().unwrap()), Some(&today));
+    let text = status
+        .render(
+            "{description} {tags} {start} {elapsed} {{{day_total}}}",
+            "%H:%M",
+        )
+        .unwrap();
+    assert_eq!(text, "Write docs #docs 09:00 0:45 {1:45}");
+    assert_eq!(status.waybar(text, "%H:%M")["class"], "active");
+    assert!(matches!(
+        status.render("{duration}", "%H:%M"),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn status_without_a_task_leaves_the_task_fields_empty() {
+    let tracker = Tracker::new();
+    let (days, _) = tracker.at("09:00:00");
+    assert!(days.find(datetime("09:00:00").date()).unwrap().is_none());
+
+    let status = Status::new(None, None);
+    assert_eq!(
+        status
+            .render("[{description}] {day_total}", "%H:%M")
+            .unwrap(),
+        "[] 0:00"
+    );
+    assert_eq!(status.i3blocks(String::new())["short_text"], "");
+}
+
+#[test]
+fn totals_are_rounded_per_task_group_or_day() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "review");
+    tracker.start("09:07:00", "docs");
+    tracker.start("09:27:00", "Review");
+    let (days, tasks) = tracker.at("09:35:00");
+    tasks.stop().unwrap();
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(day.delta(), TimeDelta::minutes(35));
+
+    let rounding = Rounding::new(6, RoundingMode::Up);
+    let total = |per| {
+        let day = day.clone().with_rounding(rounding.with_scope(per));
+        (day.delta().num_minutes(), day.raw_delta().num_minutes())
+    };
+    // 7, 20 and 8 minutes, review has 15 minutes in total
+    assert_eq!(total(RoundingScope::Task), (48, 35));
+    assert_eq!(total(RoundingScope::Group), (42, 35));
+    assert_eq!(total(RoundingScope::Day), (36, 35));
+
+    let date = day.day().date();
+    let report = Report::new(
+        date,
+        date,
+        ReportGrouping::Description,
+        &[day],
+        rounding.with_scope(RoundingScope::Task),
+        Weekday::Mon,
+    );
+    assert_eq!(report.total(), TimeDelta::minutes(48));
+    assert_eq!(report.raw_total(), TimeDelta::minutes(35));
+    let review = report
+        .groups()
+        .find(|group| group.key() == "review")
+        .unwrap();
+    assert_eq!(review.delta(), TimeDelta::minutes(24));
+    assert_eq!(review.raw_delta(), TimeDelta::minutes(15));
+}
+
+#[test]
+fn range_only_returns_existing_days() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    let (days, _) = tracker.at("10:00:00");
+    let today = datetime("10:00:00").date();
+    let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+    let range = days.range(from, today).unwrap();
+    assert_eq!(range.len(), 1);
+    assert_eq!(range[0].date(), today);
+    assert!(days.find(from).unwrap().is_none());
+    assert!(days
+        .range(today.succ_opt().unwrap(), today)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn export_splits_tasks_at_midnight() {
+    let tracker = Tracker::new();
+    tracker.start("22:30:00", "deploy");
+    let next_day = datetime("00:00:00") + TimeDelta::days(1);
+    let clock = Clock::Fixed(next_day + TimeDelta::minutes(90));
+    let days = DayRepository::new(tracker.connection.clone(), clock);
+    let tasks = TaskRepository::new(tracker.connection.clone(), tracker.journal.clone(), clock);
+    tasks.stop_at(next_day + TimeDelta::minutes(30)).unwrap();
+    tasks
+        .start(days.today().unwrap(), "review", &["ttrace".to_owned()])
+        .unwrap();
+
+    let range = days
+        .range(datetime("00:00:00").date(), next_day.date())
+        .unwrap()
+        .into_iter()
+        .map(|day| tasks.day_with_tasks(day).unwrap())
+        .collect::<Vec<_>>();
+    let mut csv = Vec::new();
+    write_csv(&mut csv, &range).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "id,date,start,end,minutes,hours,description,tags,running\r\n\
+         1,2024-05-01,22:30,24:00,90,1.50,deploy,,false\r\n\
+         1,2024-05-02,00:00,00:30,30,0.50,deploy,,false\r\n\
+         2,2024-05-02,01:30,,0,0.00,review,ttrace,true\r\n"
+    );
+}
+
+#[test]
+fn summaries_and_reports_split_weeks_at_the_week_start() {
+    let tracker = Tracker::new();
+    // from saturday to monday, one hour per day
+    let first = NaiveDate::from_ymd_opt(2024, 5, 4).unwrap();
+    let clock = Clock::Fixed(first.and_hms_opt(23, 0, 0).unwrap() + TimeDelta::days(2));
+    let days = DayRepository::new(tracker.connection.clone(), clock);
+    let tasks = TaskRepository::new(tracker.connection.clone(), tracker.journal.clone(), clock);
+    for date in first.iter_days().take(3) {
+        let start = date.and_hms_opt(9, 0, 0).unwrap();
+        let day = days.from_date(date).unwrap();
+        tasks
+            .add(day, start, start + TimeDelta::hours(1), "review", &[])
+            .unwrap();
+    }
+    let last = first + TimeDelta::days(2);
+    let range: Vec<_> = days
+        .range(first, last)
+        .unwrap()
+        .into_iter()
+        .map(|day| tasks.day_with_tasks(day).unwrap())
+        .collect();
+
+    let weeks = |week_start| {
+        let summary = Summary::new(first, last, &range, week_start);
+        let weeks: Vec<_> = summary
+            .weeks()
+            .map(|week| (week.start(), week.days().count()))
+            .collect();
+        let report = Report::new(
+            first,
+            last,
+            ReportGrouping::Week,
+            &range,
+            Rounding::default(),
+            week_start,
+        );
+        let keys: Vec<_> = report
+            .groups()
+            .map(|group| group.key().to_owned())
+            .collect();
+        (weeks, keys)
+    };
+    let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+    assert_eq!(
+        weeks(Weekday::Mon),
+        (
+            vec![(date(4, 29), 2), (date(5, 6), 1)],
+            vec!["2024-04-29".to_owned(), "2024-05-06".to_owned()]
+        )
+    );
+    assert_eq!(
+        weeks(Weekday::Sun),
+        (
+            vec![(date(4, 28), 1), (date(5, 5), 2)],
+            vec!["2024-04-28".to_owned(), "2024-05-05".to_owned()]
+        )
+    );
+}
+
+#[test]
+fn start_at_stops_the_running_task_at_that_time() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+
+    let (days, tasks) = tracker.at("10:30:00");
+    let today = days.today().unwrap();
+    let task = tasks
+        .start_at(today, datetime("10:20:00"), "review", &[])
+        .unwrap();
+    assert_eq!(task.start(), datetime("10:20:00"));
+    let previous = tasks.prev(&task).unwrap().unwrap();
+    assert_eq!(previous.end(), Some(datetime("10:20:00")));
+
+    let today = days.today().unwrap();
+    assert!(matches!(
+        tasks.start_at(today, datetime("10:10:00"), "docs", &[]),
+        Err(Error::InvalidTime(_))
+    ));
+    assert!(matches!(
+        tasks.start_at(today, datetime("11:00:00"), "docs", &[]),
+        Err(Error::InvalidTime(_))
+    ));
+}
+
+#[test]
+fn start_at_trims_finished_tasks_but_does_not_cover_them() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    let (days, tasks) = tracker.at("10:00:00");
+    tasks.stop().unwrap();
+
+    let (days_later, tasks_later) = tracker.at("10:30:00");
+    let today = days_later.today().unwrap();
+    assert!(matches!(
+        tasks_later.start_at(today, datetime("08:30:00"), "review", &[]),
+        Err(Error::Overlap(_))
+    ));
+    let task = tasks_later
+        .start_at(today, datetime("09:45:00"), "review", &[])
+        .unwrap();
+    let previous = tasks.prev(&task).unwrap().unwrap();
+    assert_eq!(previous.end(), Some(datetime("09:45:00")));
+    assert_eq!(days.today().unwrap().id(), task.day().id());
+}
+
+#[test]
+fn stop_at_ends_the_running_task_between_its_start_and_now() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+
+    let (_, tasks) = tracker.at("12:30:00");
+    for time in ["08:59:00", "09:00:00", "12:31:00"] {
+        assert!(matches!(
+            tasks.stop_at(datetime(time)),
+            Err(Error::InvalidTime(_))
+        ));
+    }
+    let task = tasks.stop_at(datetime("12:15:00")).unwrap();
+    assert_eq!(task.end(), Some(datetime("12:15:00")));
+    assert!(matches!(tasks.current(), Err(Error::NoActiveTask)));
+}
+
+#[test]
+fn split_continues_the_task_without_a_gap() {
+    let tracker = Tracker::new();
+    let (days, tasks) = tracker.at("09:00:00");
+    tasks
+        .start(days.today().unwrap(), "write tests", &["ttrace".to_owned()])
+        .unwrap();
+
+    let (days, tasks) = tracker.at("12:00:00");
+    let task = tasks.current().unwrap();
+    let day = days.today().unwrap();
+    assert!(matches!(
+        tasks.split(task.clone(), day, datetime("12:30:00"), None),
+        Err(Error::InvalidTime(_))
+    ));
+    let (first, second) = tasks
+        .split(task, day, datetime("11:00:00"), Some("review"))
+        .unwrap();
+    assert_eq!(first.end(), Some(datetime("11:00:00")));
+    assert_eq!(second.start(), datetime("11:00:00"));
+    assert_eq!(second.end(), None);
+    assert_eq!(second.description(), "review");
+    assert_eq!(second.tags(), ["ttrace"]);
+    assert_eq!(tasks.current().unwrap().id(), second.id());
+
+    let (_, third) = tasks.split(first, day, datetime("10:00:00"), None).unwrap();
+    assert_eq!(third.description(), "write tests");
+    assert_eq!(third.end(), Some(datetime("11:00:00")));
+    let day = tasks.day_with_tasks(day).unwrap();
+    assert_eq!(day.tasks().count(), 3);
+    assert_eq!(day.raw_delta(), TimeDelta::hours(3));
+}
+
+#[test]
+fn merge_combines_tasks_and_absorbs_gaps_only_on_request() {
+    let tracker = Tracker::new();
+    let (days, tasks) = tracker.at("18:00:00");
+    let add = |start: &str, end: &str, description: &str, tag: &str| {
+        let day = days.today().unwrap();
+        tasks
+            .add(
+                day,
+                datetime(start),
+                datetime(end),
+                description,
+                &[tag.to_owned()],
+            )
+            .unwrap()
+    };
+    let first = add("09:00:00", "10:00:00", "write tests", "a");
+    let second = add("10:00:00", "10:30:00", "write tests", "b");
+    let third = add("11:00:00", "11:30:00", "write tests", "c");
+
+    assert!(matches!(
+        tasks.merge(vec![first.clone()], false),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        tasks.merge(vec![second.clone(), third.clone()], false),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        tasks.merge(vec![first.clone(), third.clone()], true),
+        Err(Error::Overlap(_))
+    ));
+
+    let merged = tasks.merge(vec![third, second, first], true).unwrap();
+    assert_eq!(merged.start(), datetime("09:00:00"));
+    assert_eq!(merged.end(), Some(datetime("11:30:00")));
+    assert_eq!(merged.tags(), ["a", "b", "c"]);
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    assert_eq!(day.tasks().count(), 1);
+}
+
+#[test]
+fn compact_merges_consecutive_tasks_with_the_same_description() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "Write Tests");
+    tracker.start("11:00:00", "review");
+    tracker.start("12:00:00", "write tests");
+
+    let (days, tasks) = tracker.at("13:00:00");
+    let merged = tasks.compact(days.today().unwrap(), false).unwrap();
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].start(), datetime("09:00:00"));
+    assert_eq!(merged[0].end(), Some(datetime("11:00:00")));
+
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    let descriptions: Vec<_> = day.tasks().map(|task| task.description()).collect();
+    assert_eq!(descriptions, ["write tests", "review", "write tests"]);
+    assert!(tasks.current().unwrap().is_active());
+    assert!(tasks
+        .compact(days.today().unwrap(), false)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn check_reports_and_repairs_inconsistent_tasks() {
+    let tracker = Tracker::new();
+    tracker.start("09:00:00", "write tests");
+    tracker.start("10:00:00", "review");
+    tracker.start("11:00:00", "lunch");
+    tracker.start("13:00:00", "write docs");
+    tracker
+        .connection
+        .execute_batch(
+            "UPDATE tasks SET end = '2024-05-01 10:30:00' WHERE description = 'write tests';
+             UPDATE tasks SET end = NULL WHERE description = 'review';
+             UPDATE tasks SET end = start WHERE description = 'lunch';
+             INSERT INTO tasks (day_id, start, end, description)
+             VALUES (99, '2024-04-30 08:00:00', '2024-04-30 09:00:00', 'plan');",
+        )
+        .unwrap();
+
+    let (days, tasks) = tracker.at("14:00:00");
+    let issues = check(&days, &tasks, false).unwrap();
+    let kinds: Vec<_> = issues.iter().map(|issue| issue.kind()).collect();
+    assert_eq!(
+        kinds,
+        [
+            IssueKind::OrphanedDay,
+            IssueKind::InvalidDuration,
+            IssueKind::MultipleActive,
+            IssueKind::Overlap
+        ]
+    );
+    assert!(issues.iter().all(|issue| !issue.is_fixed()));
+
+    let issues = check(&days, &tasks, true).unwrap();
+    assert_eq!(issues.len(), 4);
+    assert!(issues.iter().all(|issue| issue.is_fixed()));
+    assert!(check(&days, &tasks, false).unwrap().is_empty());
+
+    let day = tasks.day_with_tasks(days.today().unwrap()).unwrap();
+    let ends: Vec<_> = day.tasks().map(|task| task.end()).collect();
+    assert_eq!(
+        ends,
+        [Some(datetime("10:00:00")), Some(datetime("13:00:00")), None]
+    );
+    let yesterday = tasks.day_with_tasks(days.yesterday().unwrap()).unwrap();
+    assert_eq!(yesterday.tasks().count(), 1);
+}